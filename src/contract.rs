@@ -1,24 +1,100 @@
+use bech32::{FromBase32, ToBase32, Variant};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coins, to_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Empty, Env, MessageInfo,
-    Response, StdError, StdResult, Storage, Uint128,
+    coins, from_binary, to_binary, Addr, BankMsg, Binary, BlockInfo, Coin, ContractInfoResponse,
+    CosmosMsg, Decimal, Deps, DepsMut, DistributionMsg, Empty, Env, Isqrt, MessageInfo, Order,
+    Reply, Response, StakingMsg, StdError, StdResult, Storage, SubMsg, SubMsgResult, Timestamp,
+    Uint128, Uint256, WasmMsg, WasmQuery,
 };
 use cw2::{get_contract_version, set_contract_version};
-use cw_storage_plus::Map;
+use cw_storage_plus::{Bound, Map};
 use semver::Version;
+use sha2::{Digest, Sha256};
 
 use crate::error::ContractError;
 use crate::msg::{
-    BeneficiaryListResponse, BeneficiaryResponse, DonatorListResponse, ExecuteMsg, InstantiateMsg,
-    MigrateMsg, PotDonatorResponse, QueryMsg,
+    AnonymousDonation, AnonymousDonationsResponse, AuctionListResponse, BeneficiaryListResponse,
+    BeneficiaryResponse, BondResponse, CapStatus, ClaimableResponse, Cw721ExecuteMsg,
+    Cw721ReceiveMsg, DonationListResponse, DonatorListResponse, EndowmentResponse,
+    EpochConfigResponse, EpochListResponse, ExecuteMsg, FreezeListResponse,
+    GovernanceConfigResponse, GracePeriodResponse, HeldNftListResponse, IbcLifecycleComplete,
+    IbcOrigin, InstantiateMsg, LeaderboardResponse, MemoConfigResponse, MigrateMsg,
+    NftCollectionsResponse, PausedResponse, PendingOperationsResponse, PotDonatorResponse,
+    ProposalListResponse, QueryMsg, ReceiptConfigResponse, ReceiptMetadata, ReceiveNftMsg,
+    RegistrationConfigResponse, RoleHolders, RoleHoldersResponse, RoundMatch, RoundMatchesResponse,
+    SimulateDonationResponse, SimulateSwapResponse, StatsResponse, SudoMsg, SwapConfigResponse,
+    SwapRouterExecuteMsg, SwapRouterQueryMsg, UnaccountedBalanceResponse,
+};
+use crate::state::{
+    beneficiary_leaderboard, donator_leaderboard, Auction, BeneficiaryProfile, Bid,
+    DenomPreference, DistributionStrategy, Donation, DonationOptions, DonatorRefunds,
+    EndowmentConfig, EndowmentLedger, Epoch, EpochConfig, Freeze, FreezeResolution,
+    GovernanceConfig, HeldNft, IbcTransfer, Leaderboard, LeaderboardEntry, MemoConfig, Payout,
+    PendingOperation, PendingSwap, Proposal, ProposalAction, ProposalStatus, ReceiptConfig,
+    RegistrationConfig, RemoteAccount, Role, Round, State, Stats, SwapConfig, SwapRecord, VoterSet,
+    ANONYMOUS_DONATIONS, AUCTIONS, AUCTION_COUNT, BENEFICIARIES, BENEFICIARY_DONATIONS,
+    BENEFICIARY_FORWARDS, BENEFICIARY_PAID, BENEFICIARY_PAYERS, BONDS, CAPS, CLAIMABLE,
+    COLLECTED_FEES, DENOM_PREFERENCES, DISPUTE_PERIOD, DONATIONS, DONATION_COUNT, DONATORS,
+    DONATOR_DONATIONS, ENDOWMENT_CONFIG, ENDOWMENT_LEDGER, EPOCHS, EPOCH_CONFIG, EPOCH_COUNT,
+    ESCROWED_BIDS, EXPIRATIONS, FREEZES, FREEZE_COUNT, FROZEN_BENEFICIARIES, GOVERNANCE,
+    GRACE_PERIOD, HELD_NFTS, IBC_TIMEOUT, IBC_TRANSFERS, IBC_TRANSFER_QUEUE, MEMO_CONFIG,
+    NEXT_DISTRIBUTION, NFT_COLLECTIONS, OPERATION_COUNT, PAUSED, PENDING_ADDRESS_CHANGES,
+    PENDING_OPERATIONS, PENDING_POT, PENDING_REFUNDS, PENDING_SWAP, PROFILES, PROPOSALS,
+    PROPOSAL_COUNT, RECEIPT_CONFIG, REGISTRATION_CONFIG, REMOTE_ACCOUNTS, REMOVED_BENEFICIARIES,
+    ROLES, ROUNDS, ROUND_BENEFICIARIES, ROUND_CONTRIBUTIONS, ROUND_COUNT, ROUND_DONATORS,
+    ROUND_FUNDERS, ROUND_ROBIN_CURSOR, STATE, STATS, SWAP_CONFIG, TIMELOCK_DELAY,
+    TOTAL_VOTING_WEIGHTS, UNSEQUENCED_TRANSFERS, VOTES, VOTING_WEIGHTS,
 };
-use crate::state::{State, BENEFICIARIES, DONATORS, REMOVED_BENEFICIARIES, STATE};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cosmos-fanout";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// Contributions are scaled before taking their square root so small amounts keep some precision
+const QF_PRECISION: u128 = 1_000_000_000_000;
+
+// Beneficiary profile size limits
+const MAX_DISPLAY_NAME_LENGTH: usize = 64;
+const MAX_DESCRIPTION_LENGTH: usize = 1024;
+const MAX_URI_LENGTH: usize = 256;
+const MAX_TAGS: usize = 10;
+const MAX_TAG_LENGTH: usize = 32;
+
+// Reply id of the swaps made before distributing a donation
+const SWAP_REPLY_ID: u64 = 1;
+// Reply id of the transfers made to remote beneficiaries
+const IBC_TRANSFER_REPLY_ID: u64 = 2;
+const IBC_TRANSFER_TYPE_URL: &str = "/ibc.applications.transfer.v1.MsgTransfer";
+
+// Seconds before a transfer to a remote beneficiary times out, unless configured
+const DEFAULT_IBC_TIMEOUT: u64 = 600;
+
+// ibc-hooks derives the contract caller from this prefix, the channel and the original sender
+const IBC_HOOKS_SENDER_PREFIX: &str = "ibc-wasm-hook-intermediary";
+// Donators from other chains are recorded as "ibc/<channel>/<sender>"
+const REMOTE_DONATOR_PREFIX: &str = "ibc/";
+// Stands for the donator in anonymous donation records
+const ANONYMOUS_DONATOR: &str = "anonymous";
+
+// Seconds before a freeze can be resolved against the beneficiary, unless configured
+const DEFAULT_DISPUTE_PERIOD: u64 = 7 * 24 * 3600;
+
+// Donators refunded by a single call once a freeze is resolved
+const DEFAULT_REFUNDS_LIMIT: u32 = 30;
+const MAX_REFUNDS_LIMIT: u32 = 100;
+
+const DEFAULT_LEADERBOARD_LIMIT: u32 = 10;
+const MAX_LEADERBOARD_LIMIT: u32 = 30;
+
+// Amount of beneficiaries visited by a single cleanup
+const DEFAULT_CLEANUP_LIMIT: u32 = 30;
+const MAX_CLEANUP_LIMIT: u32 = 100;
+
+// Donation / epoch history pagination
+const DEFAULT_DONATIONS_LIMIT: u32 = 10;
+const MAX_DONATIONS_LIMIT: u32 = 30;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -30,6 +106,7 @@ pub fn instantiate(
     let state = State {
         owner: info.sender.clone(),
         only_owner_can_register_beneficiary: msg.only_owner_can_register_beneficiary,
+        distribution_strategy: DistributionStrategy::Equal,
     };
     STATE.save(deps.storage, &state)?;
 
@@ -45,6 +122,14 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
 
     if storage_version < version {
         set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+        // Leaderboards were added by 0.5.0, earlier versions only kept the totals
+        if storage_version < Version::new(0, 5, 0) {
+            rebuild_leaderboards(deps.storage)?;
+        }
+    }
+    // Stats were added by 0.5.0, they are computed once from what earlier versions kept
+    if STATS.may_load(deps.storage)?.is_none() {
+        backfill_stats(deps.storage)?;
     }
     Ok(Response::default())
 }
@@ -52,7 +137,26 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    if is_pausable(&msg) && PAUSED.may_load(deps.storage)?.unwrap_or_default() {
+        return Err(ContractError::Paused {});
+    }
+    // Sensitive operations made by privileged accounts are delayed while a timelock is set
+    if let Some(role) = timelocked_role(&msg) {
+        let delay = TIMELOCK_DELAY.may_load(deps.storage)?.unwrap_or_default();
+        if delay > 0 && has_role(deps.storage, &info.sender, role)? {
+            return queue_operation(deps, env, info, msg, delay);
+        }
+    }
+    dispatch(deps, env, info, msg)
+}
+
+fn dispatch(
+    deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
@@ -60,841 +164,9153 @@ pub fn execute(
     // Will reroute the message to the correct handler
     match msg {
         ExecuteMsg::RegisterBeneficiaryAsOwner { beneficiary } => {
-            register_beneficiary(deps, info, beneficiary)
+            register_beneficiary(deps, env, info, beneficiary)
         }
         ExecuteMsg::RegisterBeneficiary {} => {
-            register_beneficiary(deps, info.clone(), info.sender.clone().to_string())
+            register_beneficiary(deps, env, info.clone(), info.sender.clone().to_string())
         }
         ExecuteMsg::RemoveBeneficiary {} => {
-            remove_beneficiary(deps, info.clone(), info.sender.clone().to_string())
+            remove_beneficiary(deps, env, info.clone(), info.sender.clone().to_string())
         }
         ExecuteMsg::RemoveBeneficiaryAsOwner { beneficiary } => {
-            remove_beneficiary(deps, info.clone(), beneficiary)
+            remove_beneficiary(deps, env, info.clone(), beneficiary)
+        }
+        ExecuteMsg::AddToPot {
+            ibc_origin,
+            memo,
+            anonymous,
+            min_output,
+        } => add_to_pot(deps, env, info, ibc_origin, memo, anonymous, min_output),
+        ExecuteMsg::DiscloseDonation { donation_id, salt } => {
+            disclose_donation(deps, env, info, donation_id, salt)
+        }
+        ExecuteMsg::CreateRound { start, end, denom } => {
+            create_round(deps, env, info, start, end, denom)
+        }
+        ExecuteMsg::FundRound { round_id } => fund_round(deps, info, round_id),
+        ExecuteMsg::ContributeToRound {
+            round_id,
+            beneficiary,
+        } => contribute_to_round(deps, env, info, round_id, beneficiary),
+        ExecuteMsg::FinalizeRound { round_id } => finalize_round(deps, env, round_id),
+        ExecuteMsg::ReclaimMatchingPool { round_id } => reclaim_matching_pool(deps, info, round_id),
+        ExecuteMsg::SetGovernanceConfig { config } => {
+            set_governance_config(deps, env, info, config)
+        }
+        ExecuteMsg::Propose { action } => propose(deps, env, info, action),
+        ExecuteMsg::Vote {
+            proposal_id,
+            approve,
+        } => vote(deps, env, info, proposal_id, approve),
+        ExecuteMsg::ExecuteProposal { proposal_id } => execute_proposal(deps, env, proposal_id),
+        ExecuteMsg::GrantRole { role, address } => grant_role(deps, info, role, address),
+        ExecuteMsg::RevokeRole { role, address } => revoke_role(deps, info, role, address),
+        ExecuteMsg::Pause {} => set_paused(deps, info, true),
+        ExecuteMsg::Unpause {} => set_paused(deps, info, false),
+        ExecuteMsg::SetTimelockDelay { delay } => set_timelock_delay(deps, info, delay),
+        ExecuteMsg::ExecuteOperation { operation_id } => execute_operation(deps, env, operation_id),
+        ExecuteMsg::CancelOperation { operation_id } => cancel_operation(deps, info, operation_id),
+        ExecuteMsg::SetBeneficiaryProfile {
+            beneficiary,
+            profile,
+        } => set_beneficiary_profile(deps, info, beneficiary, profile),
+        ExecuteMsg::ChangeBeneficiaryAddress { new_address } => {
+            change_beneficiary_address(deps, info, new_address)
+        }
+        ExecuteMsg::ConfirmBeneficiaryAddress { old_address } => {
+            confirm_beneficiary_address(deps, env, info, old_address)
+        }
+        ExecuteMsg::SetRegistrationConfig { config } => set_registration_config(deps, info, config),
+        ExecuteMsg::WithdrawFees { to } => withdraw_fees(deps, info, to),
+        ExecuteMsg::RenewRegistration {} => renew_registration(deps, env, info),
+        ExecuteMsg::CleanupExpiredBeneficiaries { start_after, limit } => {
+            cleanup_expired_beneficiaries(deps, env, start_after, limit)
+        }
+        ExecuteMsg::SetBeneficiaryCaps { beneficiary, caps } => {
+            set_beneficiary_caps(deps, info, beneficiary, caps)
         }
-        ExecuteMsg::AddToPot {} => add_to_pot(deps, info),
+        ExecuteMsg::SetDenomPreference {
+            beneficiary,
+            preference,
+        } => set_denom_preference(deps, info, beneficiary, preference),
+        ExecuteMsg::SetDistributionStrategy { strategy } => {
+            set_distribution_strategy(deps, info, strategy)
+        }
+        ExecuteMsg::SetEpochConfig { config } => set_epoch_config(deps, info, config),
+        ExecuteMsg::Distribute {} => distribute(deps, env),
+        ExecuteMsg::SetSwapConfig { config } => set_swap_config(deps, info, config),
+        ExecuteMsg::SetEndowmentConfig { config } => set_endowment_config(deps, info, config),
+        ExecuteMsg::HarvestRewards {} => harvest_rewards(deps, env),
+        ExecuteMsg::Delegate { validator, amount } => delegate(deps, env, info, validator, amount),
+        ExecuteMsg::Redelegate {
+            src_validator,
+            dst_validator,
+            amount,
+        } => redelegate(deps, env, info, src_validator, dst_validator, amount),
+        ExecuteMsg::Undelegate { validator, amount } => {
+            undelegate(deps, env, info, validator, amount)
+        }
+        ExecuteMsg::SetRemoteAccount {
+            beneficiary,
+            remote,
+        } => set_remote_account(deps, info, beneficiary, remote),
+        ExecuteMsg::SetIbcTimeout { seconds } => set_ibc_timeout(deps, info, seconds),
+        ExecuteMsg::ClaimPayouts {} => claim_payouts(deps, info),
+        ExecuteMsg::SetReceiptConfig { config } => set_receipt_config(deps, info, config),
+        ExecuteMsg::SetMemoConfig { config } => set_memo_config(deps, info, config),
+        ExecuteMsg::ReceiveNft(msg) => receive_nft(deps, env, info, msg),
+        ExecuteMsg::SetNftCollections { collections } => {
+            set_nft_collections(deps, info, collections)
+        }
+        ExecuteMsg::StartAuction {
+            collection,
+            token_id,
+            reserve_price,
+            duration,
+        } => start_auction(
+            deps,
+            env,
+            info,
+            collection,
+            token_id,
+            reserve_price,
+            duration,
+        ),
+        ExecuteMsg::Bid { auction_id } => bid(deps, env, info, auction_id),
+        ExecuteMsg::SettleAuction { auction_id } => settle_auction(deps, env, auction_id),
+        ExecuteMsg::DistributeRoyalties {} => distribute_royalties(deps, env),
+        ExecuteMsg::SetGracePeriod { seconds } => set_grace_period(deps, info, seconds),
+        ExecuteMsg::RetractDonation { donation_id, salt } => {
+            retract_donation(deps, env, info, donation_id, salt)
+        }
+        ExecuteMsg::FreezeBeneficiary {
+            beneficiary,
+            reason,
+        } => freeze_beneficiary(deps, env, info, beneficiary, reason),
+        ExecuteMsg::ResolveFreeze {
+            freeze_id,
+            resolution,
+        } => resolve_freeze(deps, env, info, freeze_id, resolution),
+        ExecuteMsg::RefundDonators { freeze_id, limit } => refund_donators(deps, freeze_id, limit),
+        ExecuteMsg::SetDisputePeriod { seconds } => set_dispute_period(deps, info, seconds),
+        ExecuteMsg::Sweep { denom, to } => sweep(deps, env, info, denom, to),
+    }
+}
+
+// Role a sender needs to hold for the message to be subject to the timelock
+fn timelocked_role(msg: &ExecuteMsg) -> Option<Role> {
+    match msg {
+        ExecuteMsg::RegisterBeneficiaryAsOwner { .. }
+        | ExecuteMsg::RemoveBeneficiaryAsOwner { .. }
+        | ExecuteMsg::SetBeneficiaryCaps { .. }
+        // Redirecting or cutting off someone else's payouts
+        | ExecuteMsg::SetRemoteAccount {
+            beneficiary: Some(_),
+            ..
+        }
+        | ExecuteMsg::SetDenomPreference {
+            beneficiary: Some(_),
+            ..
+        } => Some(Role::BeneficiaryManager),
+        ExecuteMsg::SetGovernanceConfig { .. }
+        | ExecuteMsg::GrantRole { .. }
+        | ExecuteMsg::RevokeRole { .. }
+        | ExecuteMsg::SetTimelockDelay { .. }
+        | ExecuteMsg::SetDistributionStrategy { .. }
+        | ExecuteMsg::SetEpochConfig { .. }
+        | ExecuteMsg::SetSwapConfig { .. }
+        | ExecuteMsg::SetEndowmentConfig { .. }
+        | ExecuteMsg::Delegate { .. }
+        | ExecuteMsg::Redelegate { .. }
+        | ExecuteMsg::Undelegate { .. }
+        | ExecuteMsg::SetIbcTimeout { .. }
+        | ExecuteMsg::SetReceiptConfig { .. }
+        | ExecuteMsg::SetMemoConfig { .. }
+        | ExecuteMsg::SetGracePeriod { .. }
+        | ExecuteMsg::ResolveFreeze { .. }
+        | ExecuteMsg::SetDisputePeriod { .. }
+        | ExecuteMsg::Sweep { .. }
+        | ExecuteMsg::SetNftCollections { .. }
+        | ExecuteMsg::StartAuction { .. } => Some(Role::Admin),
+        ExecuteMsg::SetRegistrationConfig { .. } | ExecuteMsg::WithdrawFees { .. } => {
+            Some(Role::FeeManager)
+        }
+        _ => None,
+    }
+}
+
+// Messages moving funds to or from the pot, rejected while paused
+fn is_pausable(msg: &ExecuteMsg) -> bool {
+    matches!(
+        msg,
+        ExecuteMsg::AddToPot { .. }
+            | ExecuteMsg::FundRound { .. }
+            | ExecuteMsg::ContributeToRound { .. }
+            | ExecuteMsg::FinalizeRound { .. }
+            | ExecuteMsg::ReclaimMatchingPool { .. }
+            | ExecuteMsg::Distribute {}
+            | ExecuteMsg::HarvestRewards {}
+            | ExecuteMsg::ClaimPayouts {}
+            | ExecuteMsg::ReceiveNft(..)
+            | ExecuteMsg::Bid { .. }
+            | ExecuteMsg::SettleAuction { .. }
+            | ExecuteMsg::DistributeRoyalties {}
+            | ExecuteMsg::RefundDonators { .. }
+    )
+}
+
+fn queue_operation(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+    delay: u64,
+) -> Result<Response, ContractError> {
+    // Queued operations are replayed without funds
+    if !info.funds.is_empty() {
+        return Err(ContractError::InvalidFunds {});
+    }
+    let operation_id = OPERATION_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    OPERATION_COUNT.save(deps.storage, &operation_id)?;
+    let operation = PendingOperation {
+        id: operation_id,
+        proposer: info.sender,
+        msg,
+        executable_at: env.block.time.plus_seconds(delay),
+    };
+    PENDING_OPERATIONS.save(deps.storage, operation_id, &operation)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "queue_operation")
+        .add_attribute("operation_id", operation_id.to_string())
+        .add_attribute("executable_at", operation.executable_at.to_string()))
+}
+
+pub fn set_timelock_delay(
+    deps: DepsMut,
+    info: MessageInfo,
+    delay: u64,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    TIMELOCK_DELAY.save(deps.storage, &delay)?;
+    Ok(Response::new()
+        .add_attribute("method", "set_timelock_delay")
+        .add_attribute("delay", delay.to_string()))
+}
+
+pub fn execute_operation(
+    deps: DepsMut,
+    env: Env,
+    operation_id: u64,
+) -> Result<Response, ContractError> {
+    let operation = PENDING_OPERATIONS.load(deps.storage, operation_id)?;
+    if env.block.time < operation.executable_at {
+        return Err(ContractError::OperationNotReady {});
+    }
+    PENDING_OPERATIONS.remove(deps.storage, operation_id);
+
+    // Replayed on behalf of the proposer, so its privileges are checked once again
+    let info = MessageInfo {
+        sender: operation.proposer,
+        funds: vec![],
+    };
+    let response = dispatch(deps, env, info, operation.msg)?;
+    Ok(response
+        .add_attribute("method", "execute_operation")
+        .add_attribute("operation_id", operation_id.to_string()))
+}
+
+pub fn cancel_operation(
+    deps: DepsMut,
+    info: MessageInfo,
+    operation_id: u64,
+) -> Result<Response, ContractError> {
+    let operation = PENDING_OPERATIONS.load(deps.storage, operation_id)?;
+    if operation.proposer != info.sender && !has_role(deps.storage, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
     }
+    PENDING_OPERATIONS.remove(deps.storage, operation_id);
+    Ok(Response::new()
+        .add_attribute("method", "cancel_operation")
+        .add_attribute("operation_id", operation_id.to_string()))
 }
 
 pub fn remove_beneficiary(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     beneficiary: String,
 ) -> Result<Response, ContractError> {
     let beneficiary_addr = deps.api.addr_validate(&beneficiary)?;
-    let state = STATE.load(deps.storage).expect("unable to load state");
-    if info.sender != beneficiary && state.owner != info.sender {
+    if info.sender != beneficiary
+        && !has_role(deps.storage, &info.sender, Role::BeneficiaryManager)?
+    {
         return Err(ContractError::Unauthorized {});
     }
-    if !BENEFICIARIES.has(deps.storage, beneficiary_addr.clone()) {
-        return Err(ContractError::NotABeneficiary {});
+    // The bond of a frozen beneficiary is at stake until the freeze is resolved
+    if FROZEN_BENEFICIARIES.has(deps.storage, beneficiary_addr.clone()) {
+        return Err(ContractError::BeneficiaryFrozen {});
     }
-    if let Ok(beneficiairies_funds) = BENEFICIARIES.load(deps.storage, beneficiary_addr.clone()) {
-        REMOVED_BENEFICIARIES.save(
-            deps.storage,
-            beneficiary_addr.clone(),
-            &beneficiairies_funds,
-        )?
+    move_beneficiary_to_removed(deps.storage, env.block.height, beneficiary_addr.clone())?;
+    EXPIRATIONS.remove(deps.storage, beneficiary_addr.clone());
+    // Bonds are only given back to beneficiaries leaving on their own
+    let voluntary = info.sender == beneficiary_addr;
+    let mut response = Response::new();
+    if let Some(refund) = release_bond(deps.storage, &beneficiary_addr, voluntary)? {
+        response = response.add_message(refund);
     }
-    BENEFICIARIES.remove(deps.storage, beneficiary_addr);
-    Ok(Response::new().add_attribute("method", "remove_beneficiary"))
+    Ok(response.add_attribute("method", "remove_beneficiary"))
 }
 
 pub fn register_beneficiary(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     beneficiary: String,
 ) -> Result<Response, ContractError> {
     let beneficiary_addr = deps.api.addr_validate(&beneficiary)?;
     let state = STATE.load(deps.storage).expect("unable to load state");
-    if state.only_owner_can_register_beneficiary && state.owner != info.sender {
+    if state.only_owner_can_register_beneficiary
+        && !has_role(deps.storage, &info.sender, Role::BeneficiaryManager)?
+    {
         return Err(ContractError::Unauthorized {});
     }
-    if BENEFICIARIES.has(deps.storage, beneficiary_addr.clone()) {
-        return Err(ContractError::AlreadyABeneficiary {});
-    }
-    // Restore old donations, useful for keeping track of all donations made to a beneficiary
-    let mut old_donations: Vec<Coin> = Vec::new();
-    if REMOVED_BENEFICIARIES.has(deps.storage, beneficiary_addr.clone()) {
-        old_donations = REMOVED_BENEFICIARIES.load(deps.storage, beneficiary_addr.clone())?;
-        REMOVED_BENEFICIARIES.remove(deps.storage, beneficiary_addr.clone());
-    }
-    let result = BENEFICIARIES.save(deps.storage, beneficiary_addr, &mut old_donations);
-    if result.is_err() {
-        return Err(ContractError::Unauthorized {});
+    // Beneficiary managers are trusted, everyone else pays the registration bond / fee
+    if !has_role(deps.storage, &info.sender, Role::BeneficiaryManager)? {
+        collect_registration_funds(deps.storage, &info, &beneficiary_addr)?;
     }
+    add_beneficiary(deps.storage, &env.block, beneficiary_addr)?;
     Ok(Response::new().add_attribute("method", "register_beneficiary"))
 }
 
-fn split_coins_into_parts(coins: &Vec<Coin>, parts: u32) -> Vec<Vec<Coin>> {
-    let mut split_coins: Vec<Vec<Coin>> = Vec::new();
-    for _ in 0..parts {
-        let mut coin_repartition = Vec::new();
-
-        for coin in coins {
-            let equal_amount_for_coin = coin.amount.checked_div(Uint128::from(parts)).unwrap();
-            let new_split_coin = Coin {
-                denom: coin.denom.clone(),
-                amount: equal_amount_for_coin,
-            };
-            coin_repartition.push(new_split_coin);
-        }
+// Checks that the exact bond + fee have been sent, then holds the bond and collects the fee
+fn collect_registration_funds(
+    storage: &mut dyn Storage,
+    info: &MessageInfo,
+    beneficiary_addr: &Addr,
+) -> Result<(), ContractError> {
+    let config = REGISTRATION_CONFIG.may_load(storage)?.unwrap_or_default();
+    if config.bond.is_none() && config.fee.is_none() {
+        return Ok(());
+    }
+    let mut expected: Vec<Coin> = Vec::new();
+    for coin in config.bond.iter().chain(config.fee.iter()) {
+        add_coin(&mut expected, coin);
+    }
+    let mut sent: Vec<Coin> = Vec::new();
+    for coin in &info.funds {
+        add_coin(&mut sent, coin);
+    }
+    expected.sort_by(|a, b| a.denom.cmp(&b.denom));
+    sent.sort_by(|a, b| a.denom.cmp(&b.denom));
+    if expected != sent {
+        return Err(ContractError::InvalidFunds {});
+    }
 
-        split_coins.push(coin_repartition);
+    if let Some(bond) = config.bond {
+        BONDS.save(storage, beneficiary_addr.clone(), &bond)?;
     }
+    if let Some(fee) = config.fee {
+        collect_fee(storage, &fee)?;
+    }
+    Ok(())
+}
 
-    return split_coins;
+fn collect_fee(storage: &mut dyn Storage, fee: &Coin) -> StdResult<()> {
+    let mut collected_fees = COLLECTED_FEES.may_load(storage)?.unwrap_or_default();
+    add_coin(&mut collected_fees, fee);
+    COLLECTED_FEES.save(storage, &collected_fees)?;
+    update_stats(storage, |stats| add_coin(&mut stats.fees_collected, fee))
 }
 
-fn register_donation_infos(
-    storage: &mut dyn Storage,
-    donator_addr: Addr,
-    mut donation_funds: Vec<Coin>,
-) {
-    let _ = DONATORS.update(
-        storage,
-        donator_addr,
-        |donator| -> Result<_, ContractError> {
-            if let Some(mut donator_funds) = donator {
-                donator_funds.append(&mut donation_funds);
-                Ok(donator_funds)
-            } else {
-                Ok(donation_funds)
-            }
-        },
-    );
+fn update_stats(storage: &mut dyn Storage, update: impl FnOnce(&mut Stats)) -> StdResult<()> {
+    let mut stats = STATS.may_load(storage)?.unwrap_or_default();
+    update(&mut stats);
+    STATS.save(storage, &stats)
 }
 
-fn register_beneficiary_donation_infos(
+// What neither the payouts nor the refund took out of the funds
+fn record_dust(
     storage: &mut dyn Storage,
-    beneficiary_addr: Addr,
-    mut donation_funds: Vec<Coin>,
-) {
-    let _ = BENEFICIARIES.update(
-        storage,
-        beneficiary_addr,
-        |beneficiary_funds| -> Result<_, ContractError> {
-            if let Some(mut beneficiary_funds) = beneficiary_funds {
-                beneficiary_funds.append(&mut donation_funds);
-                Ok(beneficiary_funds)
-            } else {
-                Ok(donation_funds)
-            }
-        },
-    );
+    funds: &[Coin],
+    payouts: &[Payout],
+    refunded: &[Coin],
+) -> StdResult<()> {
+    let mut dust: Vec<Coin> = Vec::new();
+    for coin in funds {
+        add_coin(&mut dust, coin);
+    }
+    for coin in payouts
+        .iter()
+        .flat_map(|payout| payout.amount.iter())
+        .chain(refunded.iter())
+    {
+        sub_coin(&mut dust, coin);
+    }
+    if dust.is_empty() {
+        return Ok(());
+    }
+    update_stats(storage, |stats| {
+        for coin in &dust {
+            add_coin(&mut stats.dust, coin);
+        }
+    })
 }
 
-pub fn add_to_pot(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
-    let mut amount_of_beneficiaries = 0;
-    for _beneficiary in BENEFICIARIES.keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-    {
-        amount_of_beneficiaries += 1;
+// Stats of a contract migrated from a version without them. Fees withdrawn before can't be
+// known, only those still held are counted
+fn backfill_stats(storage: &mut dyn Storage) -> StdResult<()> {
+    let mut stats = Stats {
+        fees_collected: COLLECTED_FEES.may_load(storage)?.unwrap_or_default(),
+        ..Stats::default()
+    };
+    for coin in ANONYMOUS_DONATIONS.may_load(storage)?.unwrap_or_default() {
+        add_coin(&mut stats.donated, &coin);
     }
-    if amount_of_beneficiaries < 1 {
-        return Err(ContractError::NoBeneficiaries {});
+    for item in DONATORS.range(storage, None, None, Order::Ascending) {
+        for coin in item?.1 {
+            add_coin(&mut stats.donated, &coin);
+        }
+        stats.donators += 1;
+    }
+    for item in BENEFICIARIES.range(storage, None, None, Order::Ascending) {
+        for coin in item?.1 {
+            add_coin(&mut stats.paid_out, &coin);
+        }
+        stats.registered_beneficiaries += 1;
+    }
+    for item in REMOVED_BENEFICIARIES.range(storage, None, None, Order::Ascending) {
+        for coin in item?.1 {
+            add_coin(&mut stats.paid_out, &coin);
+        }
+        stats.removed_beneficiaries += 1;
     }
-    register_donation_infos(deps.storage, info.sender.clone(), info.funds.clone());
-    let funds_for_each = split_coins_into_parts(&info.funds, amount_of_beneficiaries);
+    STATS.save(storage, &stats)
+}
 
-    // Building a new Response
-    let mut response: Response<Empty> = Response::new();
+// Refunds the bond of a leaving beneficiary, or slashes it into the collected fees
+fn release_bond(
+    storage: &mut dyn Storage,
+    beneficiary_addr: &Addr,
+    refund: bool,
+) -> StdResult<Option<BankMsg>> {
+    let bond = match BONDS.may_load(storage, beneficiary_addr.clone())? {
+        Some(bond) => bond,
+        None => return Ok(None),
+    };
+    BONDS.remove(storage, beneficiary_addr.clone());
+    if refund {
+        return Ok(Some(BankMsg::Send {
+            to_address: beneficiary_addr.to_string(),
+            amount: vec![bond],
+        }));
+    }
+    collect_fee(storage, &bond)?;
+    Ok(None)
+}
 
-    // Distributing money amongst beneficiaries
-    let mut beneficiaries_list: Vec<Addr> = Vec::new();
-    for beneficiary in BENEFICIARIES.keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-    {
-        beneficiaries_list.push(beneficiary?);
+// Adds a coin to a balance, merging it with the coin of the same denom if any
+fn add_coin(balance: &mut Vec<Coin>, coin: &Coin) {
+    if coin.amount.is_zero() {
+        return;
+    }
+    match balance.iter_mut().find(|held| held.denom == coin.denom) {
+        Some(held) => held.amount += coin.amount,
+        None => balance.push(coin.clone()),
+    }
+}
+
+fn sub_coin(balance: &mut Vec<Coin>, coin: &Coin) {
+    if let Some(held) = balance.iter_mut().find(|held| held.denom == coin.denom) {
+        held.amount = held.amount.saturating_sub(coin.amount);
     }
-    for (beneficiary, coin_part) in beneficiaries_list.iter().zip(funds_for_each) {
-        // We are adding a new "BankMsg" for each beneficiary
+    balance.retain(|held| !held.amount.is_zero());
+}
+
+pub fn set_registration_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: RegistrationConfig,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::FeeManager)?;
+    REGISTRATION_CONFIG.save(deps.storage, &config)?;
+    Ok(Response::new().add_attribute("method", "set_registration_config"))
+}
+
+pub fn withdraw_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    to: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::FeeManager)?;
+    let to_addr = deps.api.addr_validate(&to)?;
+    let collected_fees = COLLECTED_FEES.may_load(deps.storage)?.unwrap_or_default();
+    COLLECTED_FEES.save(deps.storage, &vec![])?;
+
+    let mut response = Response::new();
+    if !collected_fees.is_empty() {
         response = response.add_message(BankMsg::Send {
-            amount: coins(coin_part[0].amount.u128(), coin_part[0].denom.clone()),
-            to_address: beneficiary.clone().into_string(),
+            to_address: to_addr.to_string(),
+            amount: collected_fees,
         });
-        register_beneficiary_donation_infos(deps.storage, beneficiary.clone(), coin_part);
     }
+    Ok(response
+        .add_attribute("method", "withdraw_fees")
+        .add_attribute("to", to_addr))
+}
 
-    let beneficiaries_as_str = format!("{:?}", beneficiaries_list);
-    response = response.add_attribute("beneficiaries", beneficiaries_as_str);
-    response = response.add_attribute(
-        "amount_of_beneficiaries",
-        amount_of_beneficiaries.to_string(),
-    );
-    Ok(response.add_attribute("method", "add_to_pot"))
+// Shared by the owner / beneficiary entry points and executed governance proposals
+fn move_beneficiary_to_removed(
+    storage: &mut dyn Storage,
+    height: u64,
+    beneficiary_addr: Addr,
+) -> Result<(), ContractError> {
+    if !BENEFICIARIES.has(storage, beneficiary_addr.clone()) {
+        return Err(ContractError::NotABeneficiary {});
+    }
+    if let Ok(beneficiairies_funds) = BENEFICIARIES.load(storage, beneficiary_addr.clone()) {
+        REMOVED_BENEFICIARIES.save(storage, beneficiary_addr.clone(), &beneficiairies_funds)?
+    }
+    BENEFICIARIES.remove(storage, beneficiary_addr.clone());
+    update_voting_weight(
+        storage,
+        &VoterSet::Beneficiaries,
+        &beneficiary_addr,
+        Uint128::from(1u8),
+        true,
+        height,
+    )?;
+    update_stats(storage, |stats| {
+        stats.registered_beneficiaries = stats.registered_beneficiaries.saturating_sub(1);
+        stats.removed_beneficiaries += 1;
+    })?;
+    Ok(())
 }
 
-pub fn admin_action(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
-    STATE.update(deps.storage, |state| -> Result<_, ContractError> {
-        if info.sender != state.owner {
-            return Err(ContractError::Unauthorized {});
+fn add_beneficiary(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    beneficiary_addr: Addr,
+) -> Result<(), ContractError> {
+    if BENEFICIARIES.has(storage, beneficiary_addr.clone()) {
+        return Err(ContractError::AlreadyABeneficiary {});
+    }
+    // Restore old donations, useful for keeping track of all donations made to a beneficiary
+    let mut old_donations: Vec<Coin> = Vec::new();
+    let restored = REMOVED_BENEFICIARIES.has(storage, beneficiary_addr.clone());
+    if restored {
+        old_donations = REMOVED_BENEFICIARIES.load(storage, beneficiary_addr.clone())?;
+        REMOVED_BENEFICIARIES.remove(storage, beneficiary_addr.clone());
+    }
+    update_stats(storage, |stats| {
+        stats.registered_beneficiaries += 1;
+        if restored {
+            stats.removed_beneficiaries = stats.removed_beneficiaries.saturating_sub(1);
         }
-        Ok(state)
     })?;
-    Ok(Response::new().add_attribute("method", "admin_action"))
+    let result = BENEFICIARIES.save(storage, beneficiary_addr.clone(), &old_donations);
+    if result.is_err() {
+        return Err(ContractError::Unauthorized {});
+    }
+    update_voting_weight(
+        storage,
+        &VoterSet::Beneficiaries,
+        &beneficiary_addr,
+        Uint128::from(1u8),
+        false,
+        block.height,
+    )?;
+    set_registration_expiration(storage, block, beneficiary_addr)?;
+    Ok(())
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetState {} => to_binary(&query_state(deps)?),
-        QueryMsg::GetDonator { donator } => to_binary(&query_donator(deps, donator)?),
-        QueryMsg::GetBeneficiary { beneficiary } => {
-            to_binary(&query_beneficiary(deps, beneficiary, &BENEFICIARIES)?)
-        }
-        QueryMsg::GetRemovedBeneficiary { beneficiary } => to_binary(&query_beneficiary(
-            deps,
-            beneficiary,
-            &REMOVED_BENEFICIARIES,
-        )?),
-        QueryMsg::GetAllDonators {} => to_binary(&query_all_donators(deps)?),
-        QueryMsg::GetAllBeneficiaries {} => {
-            to_binary(&query_all_beneficiaries(deps, &BENEFICIARIES)?)
+fn set_registration_expiration(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    beneficiary_addr: Addr,
+) -> StdResult<()> {
+    let config = REGISTRATION_CONFIG.may_load(storage)?.unwrap_or_default();
+    match config.duration {
+        Some(duration) => EXPIRATIONS.save(storage, beneficiary_addr, &duration.after(block)),
+        None => {
+            EXPIRATIONS.remove(storage, beneficiary_addr);
+            Ok(())
         }
-        QueryMsg::GetAllRemovedBeneficiaries {} => {
-            to_binary(&query_all_beneficiaries(deps, &REMOVED_BENEFICIARIES)?)
+    }
+}
+
+fn is_active(storage: &dyn Storage, block: &BlockInfo, beneficiary_addr: &Addr) -> StdResult<bool> {
+    Ok(
+        match EXPIRATIONS.may_load(storage, beneficiary_addr.clone())? {
+            Some(expiration) => !expiration.is_expired(block),
+            None => true,
+        },
+    )
+}
+
+// Beneficiaries which registration has not expired, frozen ones excluded
+fn active_beneficiaries(storage: &dyn Storage, block: &BlockInfo) -> StdResult<Vec<Addr>> {
+    let mut beneficiaries = Vec::new();
+    for beneficiary in BENEFICIARIES.keys(storage, None, None, Order::Ascending) {
+        let beneficiary = beneficiary?;
+        if is_active(storage, block, &beneficiary)?
+            && !FROZEN_BENEFICIARIES.has(storage, beneficiary.clone())
+        {
+            beneficiaries.push(beneficiary);
         }
     }
+    Ok(beneficiaries)
 }
 
-fn query_state(deps: Deps) -> StdResult<State> {
-    if let Ok(state) = STATE.load(deps.storage) {
-        return Ok(State {
-            owner: state.owner,
-            only_owner_can_register_beneficiary: state.only_owner_can_register_beneficiary,
-        });
+pub fn renew_registration(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    if BENEFICIARIES.has(deps.storage, info.sender.clone()) {
+        set_registration_expiration(deps.storage, &env.block, info.sender.clone())?;
+    } else if REMOVED_BENEFICIARIES.has(deps.storage, info.sender.clone())
+        && EXPIRATIONS.has(deps.storage, info.sender.clone())
+    {
+        // Moved out on expiry, its bond has been refunded so it has to register again
+        if !has_role(deps.storage, &info.sender, Role::BeneficiaryManager)? {
+            collect_registration_funds(deps.storage, &info, &info.sender)?;
+        }
+        add_beneficiary(deps.storage, &env.block, info.sender.clone())?;
+    } else {
+        return Err(ContractError::NotABeneficiary {});
     }
-    Err(StdError::GenericErr {
-        msg: "unable to load contract state".to_string(),
-    })
+    Ok(Response::new()
+        .add_attribute("method", "renew_registration")
+        .add_attribute("beneficiary", info.sender))
 }
 
-fn query_donator(deps: Deps, donator: String) -> StdResult<PotDonatorResponse> {
-    let donator_addr = deps.api.addr_validate(&donator)?;
-    if let Ok(donator_infos) = DONATORS.load(deps.storage, donator_addr.clone()) {
-        return Ok(PotDonatorResponse {
-            donator: donator_addr,
-            donations: donator_infos,
-        });
+pub fn cleanup_expired_beneficiaries(
+    deps: DepsMut,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit
+        .unwrap_or(DEFAULT_CLEANUP_LIMIT)
+        .min(MAX_CLEANUP_LIMIT) as usize;
+    let start = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?
+        .map(Bound::exclusive);
+    let visited = BENEFICIARIES
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut expired = Vec::new();
+    for beneficiary in &visited {
+        // Frozen beneficiaries keep their bond until the freeze is resolved
+        if !is_active(deps.storage, &env.block, beneficiary)?
+            && !FROZEN_BENEFICIARIES.has(deps.storage, beneficiary.clone())
+        {
+            expired.push(beneficiary.clone());
+        }
     }
 
-    return Err(StdError::GenericErr {
-        msg: "Not a donator".to_string(),
-    });
+    let mut response = Response::new();
+    for beneficiary in &expired {
+        move_beneficiary_to_removed(deps.storage, env.block.height, beneficiary.clone())?;
+        // Expiring is not a misbehavior, the bond is given back
+        if let Some(refund) = release_bond(deps.storage, beneficiary, true)? {
+            response = response.add_message(refund);
+        }
+    }
+    // Callers resume from the last visited beneficiary, none is left once fewer than limit were visited
+    let last_visited = match visited.last() {
+        Some(last) if visited.len() == limit => last.to_string(),
+        _ => String::new(),
+    };
+    Ok(response
+        .add_attribute("method", "cleanup_expired_beneficiaries")
+        .add_attribute("removed", format!("{:?}", expired))
+        .add_attribute("last_visited", last_visited))
 }
 
-fn query_beneficiary(
-    deps: Deps,
+fn validate_profile(profile: &BeneficiaryProfile) -> Result<(), ContractError> {
+    let fields = [
+        (
+            "display_name",
+            &profile.display_name,
+            MAX_DISPLAY_NAME_LENGTH,
+        ),
+        ("description", &profile.description, MAX_DESCRIPTION_LENGTH),
+        ("url", &profile.url, MAX_URI_LENGTH),
+        ("logo_uri", &profile.logo_uri, MAX_URI_LENGTH),
+    ];
+    for (field, value, max_length) in fields {
+        if value.as_ref().map_or(0, |value| value.len()) > max_length {
+            return Err(ContractError::ProfileFieldTooLong {
+                field: field.to_string(),
+            });
+        }
+    }
+    if profile.tags.len() > MAX_TAGS {
+        return Err(ContractError::TooManyTags {});
+    }
+    if profile.tags.iter().any(|tag| tag.len() > MAX_TAG_LENGTH) {
+        return Err(ContractError::ProfileFieldTooLong {
+            field: "tags".to_string(),
+        });
+    }
+    Ok(())
+}
+
+pub fn set_beneficiary_profile(
+    deps: DepsMut,
+    info: MessageInfo,
+    beneficiary: Option<String>,
+    profile: Option<BeneficiaryProfile>,
+) -> Result<Response, ContractError> {
+    let beneficiary_addr = match beneficiary {
+        Some(beneficiary) => deps.api.addr_validate(&beneficiary)?,
+        None => info.sender.clone(),
+    };
+    if info.sender != beneficiary_addr
+        && !has_role(deps.storage, &info.sender, Role::BeneficiaryManager)?
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !BENEFICIARIES.has(deps.storage, beneficiary_addr.clone())
+        && !REMOVED_BENEFICIARIES.has(deps.storage, beneficiary_addr.clone())
+    {
+        return Err(ContractError::NotABeneficiary {});
+    }
+    match profile {
+        Some(profile) => {
+            validate_profile(&profile)?;
+            PROFILES.save(deps.storage, beneficiary_addr.clone(), &profile)?;
+        }
+        None => PROFILES.remove(deps.storage, beneficiary_addr.clone()),
+    }
+    Ok(Response::new()
+        .add_attribute("method", "set_beneficiary_profile")
+        .add_attribute("beneficiary", beneficiary_addr))
+}
+
+pub fn set_beneficiary_caps(
+    deps: DepsMut,
+    info: MessageInfo,
     beneficiary: String,
-    target: &Map<Addr, Vec<Coin>>,
-) -> StdResult<BeneficiaryResponse> {
+    caps: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::BeneficiaryManager)?;
     let beneficiary_addr = deps.api.addr_validate(&beneficiary)?;
-    if let Ok(beneficiary_infos) = target.load(deps.storage, beneficiary_addr.clone()) {
-        return Ok(BeneficiaryResponse {
-            beneficiary: beneficiary_addr,
-            received_donations: beneficiary_infos,
+    validate_caps(deps.storage, &beneficiary_addr, &caps)?;
+    if caps.is_empty() {
+        CAPS.remove(deps.storage, beneficiary_addr.clone());
+    } else {
+        CAPS.save(deps.storage, beneficiary_addr.clone(), &caps)?;
+    }
+    Ok(Response::new()
+        .add_attribute("method", "set_beneficiary_caps")
+        .add_attribute("beneficiary", beneficiary_addr))
+}
+
+fn validate_caps(
+    storage: &dyn Storage,
+    beneficiary_addr: &Addr,
+    caps: &[Coin],
+) -> Result<(), ContractError> {
+    if !BENEFICIARIES.has(storage, beneficiary_addr.clone())
+        && !REMOVED_BENEFICIARIES.has(storage, beneficiary_addr.clone())
+    {
+        return Err(ContractError::NotABeneficiary {});
+    }
+    // At most one non-zero cap per denom
+    for (i, cap) in caps.iter().enumerate() {
+        if cap.amount.is_zero() || caps[..i].iter().any(|other| other.denom == cap.denom) {
+            return Err(ContractError::InvalidCaps {});
+        }
+    }
+    Ok(())
+}
+
+pub fn set_denom_preference(
+    deps: DepsMut,
+    info: MessageInfo,
+    beneficiary: Option<String>,
+    preference: Option<DenomPreference>,
+) -> Result<Response, ContractError> {
+    let beneficiary_addr = match beneficiary {
+        Some(beneficiary) => deps.api.addr_validate(&beneficiary)?,
+        None => info.sender.clone(),
+    };
+    if info.sender != beneficiary_addr
+        && !has_role(deps.storage, &info.sender, Role::BeneficiaryManager)?
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !BENEFICIARIES.has(deps.storage, beneficiary_addr.clone())
+        && !REMOVED_BENEFICIARIES.has(deps.storage, beneficiary_addr.clone())
+    {
+        return Err(ContractError::NotABeneficiary {});
+    }
+    match preference {
+        Some(preference) => {
+            // Accepting nothing at all is done by leaving
+            if let DenomPreference::Accept { denoms } = &preference {
+                if denoms.is_empty() {
+                    return Err(ContractError::InvalidDenomPreference {});
+                }
+            }
+            DENOM_PREFERENCES.save(deps.storage, beneficiary_addr.clone(), &preference)?;
+        }
+        None => DENOM_PREFERENCES.remove(deps.storage, beneficiary_addr.clone()),
+    }
+    Ok(Response::new()
+        .add_attribute("method", "set_denom_preference")
+        .add_attribute("beneficiary", beneficiary_addr))
+}
+
+pub fn change_beneficiary_address(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_address: String,
+) -> Result<Response, ContractError> {
+    let new_addr = deps.api.addr_validate(&new_address)?;
+    if !BENEFICIARIES.has(deps.storage, info.sender.clone()) {
+        return Err(ContractError::NotABeneficiary {});
+    }
+    // Histories are moved as a whole, they can't be merged with an existing one
+    // nor can an old address be taken back, its forward would point back at itself
+    if new_addr == info.sender
+        || BENEFICIARIES.has(deps.storage, new_addr.clone())
+        || REMOVED_BENEFICIARIES.has(deps.storage, new_addr.clone())
+        || BENEFICIARY_FORWARDS.has(deps.storage, new_addr.clone())
+    {
+        return Err(ContractError::AlreadyABeneficiary {});
+    }
+    PENDING_ADDRESS_CHANGES.save(deps.storage, info.sender.clone(), &new_addr)?;
+    Ok(Response::new()
+        .add_attribute("method", "change_beneficiary_address")
+        .add_attribute("old_address", info.sender)
+        .add_attribute("new_address", new_addr))
+}
+
+pub fn confirm_beneficiary_address(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    old_address: String,
+) -> Result<Response, ContractError> {
+    let old_addr = deps.api.addr_validate(&old_address)?;
+    match PENDING_ADDRESS_CHANGES.may_load(deps.storage, old_addr.clone())? {
+        Some(new_addr) if new_addr == info.sender => {}
+        _ => return Err(ContractError::NoPendingAddressChange {}),
+    }
+    PENDING_ADDRESS_CHANGES.remove(deps.storage, old_addr.clone());
+    if !BENEFICIARIES.has(deps.storage, old_addr.clone()) {
+        return Err(ContractError::NotABeneficiary {});
+    }
+    if FROZEN_BENEFICIARIES.has(deps.storage, old_addr.clone()) {
+        return Err(ContractError::BeneficiaryFrozen {});
+    }
+    if BENEFICIARIES.has(deps.storage, info.sender.clone())
+        || REMOVED_BENEFICIARIES.has(deps.storage, info.sender.clone())
+    {
+        return Err(ContractError::AlreadyABeneficiary {});
+    }
+    move_beneficiary_records(deps.storage, env.block.height, &old_addr, &info.sender)?;
+    BENEFICIARY_FORWARDS.save(deps.storage, old_addr.clone(), &info.sender)?;
+    BENEFICIARY_FORWARDS.remove(deps.storage, info.sender.clone());
+
+    Ok(Response::new()
+        .add_attribute("method", "confirm_beneficiary_address")
+        .add_attribute("old_address", old_addr)
+        .add_attribute("new_address", info.sender))
+}
+
+// Moves everything known about a beneficiary from one address to another
+fn move_beneficiary_records(
+    storage: &mut dyn Storage,
+    height: u64,
+    old: &Addr,
+    new: &Addr,
+) -> StdResult<()> {
+    let received_donations = BENEFICIARIES.load(storage, old.clone())?;
+    BENEFICIARIES.save(storage, new.clone(), &received_donations)?;
+    BENEFICIARIES.remove(storage, old.clone());
+    let voters = VoterSet::Beneficiaries;
+    update_voting_weight(storage, &voters, old, Uint128::from(1u8), true, height)?;
+    update_voting_weight(storage, &voters, new, Uint128::from(1u8), false, height)?;
+    if let Some(profile) = PROFILES.may_load(storage, old.clone())? {
+        PROFILES.save(storage, new.clone(), &profile)?;
+        PROFILES.remove(storage, old.clone());
+    }
+    if let Some(bond) = BONDS.may_load(storage, old.clone())? {
+        BONDS.save(storage, new.clone(), &bond)?;
+        BONDS.remove(storage, old.clone());
+    }
+    if let Some(expiration) = EXPIRATIONS.may_load(storage, old.clone())? {
+        EXPIRATIONS.save(storage, new.clone(), &expiration)?;
+        EXPIRATIONS.remove(storage, old.clone());
+    }
+    if let Some(caps) = CAPS.may_load(storage, old.clone())? {
+        CAPS.save(storage, new.clone(), &caps)?;
+        CAPS.remove(storage, old.clone());
+    }
+    if let Some(preference) = DENOM_PREFERENCES.may_load(storage, old.clone())? {
+        DENOM_PREFERENCES.save(storage, new.clone(), &preference)?;
+        DENOM_PREFERENCES.remove(storage, old.clone());
+    }
+    if let Some(remote) = REMOTE_ACCOUNTS.may_load(storage, old.clone())? {
+        REMOTE_ACCOUNTS.save(storage, new.clone(), &remote)?;
+        REMOTE_ACCOUNTS.remove(storage, old.clone());
+    }
+    if let Some(claimable) = CLAIMABLE.may_load(storage, old.clone())? {
+        CLAIMABLE.save(storage, new.clone(), &claimable)?;
+        CLAIMABLE.remove(storage, old.clone());
+    }
+    let leaderboard = beneficiary_leaderboard();
+    let entries: Vec<LeaderboardEntry> = leaderboard
+        .prefix(old.clone())
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, entry)| entry))
+        .collect::<StdResult<_>>()?;
+    for entry in entries {
+        leaderboard.remove(storage, (old.clone(), entry.denom.clone()))?;
+        leaderboard.save(
+            storage,
+            (new.clone(), entry.denom.clone()),
+            &LeaderboardEntry {
+                address: new.clone(),
+                ..entry
+            },
+        )?;
+    }
+    Ok(())
+}
+
+// Follows address changes until reaching an address known by the target map, or going back
+// to an address already visited
+fn resolve_beneficiary(
+    storage: &dyn Storage,
+    target: &Map<Addr, Vec<Coin>>,
+    mut beneficiary_addr: Addr,
+) -> StdResult<Addr> {
+    let mut visited = vec![];
+    while !target.has(storage, beneficiary_addr.clone()) {
+        match BENEFICIARY_FORWARDS.may_load(storage, beneficiary_addr.clone())? {
+            Some(forward) if !visited.contains(&forward) => {
+                visited.push(beneficiary_addr);
+                beneficiary_addr = forward;
+            }
+            _ => break,
+        }
+    }
+    Ok(beneficiary_addr)
+}
+
+fn register_donation_infos(
+    storage: &mut dyn Storage,
+    height: u64,
+    donator_addr: Addr,
+    mut donation_funds: Vec<Coin>,
+) -> StdResult<()> {
+    for coin in &donation_funds {
+        update_leaderboard(storage, &donator_leaderboard(), &donator_addr, coin, false)?;
+        update_donator_voting_weight(storage, &donator_addr, coin, false, height)?;
+    }
+    if !DONATORS.has(storage, donator_addr.clone()) {
+        update_stats(storage, |stats| stats.donators += 1)?;
+    }
+    let _ = DONATORS.update(
+        storage,
+        donator_addr,
+        |donator| -> Result<_, ContractError> {
+            if let Some(mut donator_funds) = donator {
+                donator_funds.append(&mut donation_funds);
+                Ok(donator_funds)
+            } else {
+                Ok(donation_funds)
+            }
+        },
+    );
+    Ok(())
+}
+
+// Takes a retracted donation back out of the donator totals
+fn unregister_donation_infos(
+    storage: &mut dyn Storage,
+    height: u64,
+    donator_addr: Addr,
+    retracted: &[Coin],
+) -> StdResult<()> {
+    let mut donator_funds = DONATORS
+        .may_load(storage, donator_addr.clone())?
+        .unwrap_or_default();
+    for coin in retracted {
+        update_leaderboard(storage, &donator_leaderboard(), &donator_addr, coin, true)?;
+        update_donator_voting_weight(storage, &donator_addr, coin, true, height)?;
+        let mut remaining = coin.amount;
+        for donated in donator_funds
+            .iter_mut()
+            .filter(|donated| donated.denom == coin.denom)
+        {
+            let taken = remaining.min(donated.amount);
+            donated.amount -= taken;
+            remaining -= taken;
+        }
+    }
+    donator_funds.retain(|coin| !coin.amount.is_zero());
+    if donator_funds.is_empty() {
+        DONATORS.remove(storage, donator_addr);
+        return update_stats(storage, |stats| {
+            stats.donators = stats.donators.saturating_sub(1)
         });
     }
+    DONATORS.save(storage, donator_addr, &donator_funds)
+}
 
-    return Err(StdError::GenericErr {
-        msg: "Not a beneficiary".to_string(),
-    });
+// Anonymous donations are only accounted for in the pot-level bucket
+fn register_donator(
+    storage: &mut dyn Storage,
+    env: &Env,
+    donator_addr: Addr,
+    anonymous: bool,
+    donation_funds: Vec<Coin>,
+) -> StdResult<()> {
+    update_stats(storage, |stats| {
+        for coin in &donation_funds {
+            add_coin(&mut stats.donated, coin);
+        }
+    })?;
+    // Royalties are donated in the name of the contract, which is not a donator
+    if donator_addr == env.contract.address {
+        return Ok(());
+    }
+    if !anonymous {
+        return register_donation_infos(storage, env.block.height, donator_addr, donation_funds);
+    }
+    let mut anonymous_donations = ANONYMOUS_DONATIONS.may_load(storage)?.unwrap_or_default();
+    for coin in &donation_funds {
+        add_coin(&mut anonymous_donations, coin);
+    }
+    ANONYMOUS_DONATIONS.save(storage, &anonymous_donations)
+}
+
+pub fn disclose_donation(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    donation_id: u64,
+    salt: String,
+) -> Result<Response, ContractError> {
+    let mut donation = DONATIONS.load(deps.storage, donation_id)?;
+    open_commitment(&mut donation, &info.sender, &salt)?;
+
+    // What was registered for the donation moves from the anonymous bucket to the donator
+    let mut donated = donation.funds.clone();
+    for coin in donated.iter_mut() {
+        if let Some(refund) = donation
+            .refunded
+            .iter()
+            .find(|refund| refund.denom == coin.denom)
+        {
+            coin.amount -= refund.amount;
+        }
+    }
+    donated.retain(|coin| !coin.amount.is_zero());
+    unregister_anonymous_donation(deps.storage, &donated)?;
+    register_donation_infos(deps.storage, env.block.height, info.sender.clone(), donated)?;
+
+    donation.donator = info.sender.clone();
+    donation.anonymous = false;
+    DONATIONS.save(deps.storage, donation_id, &donation)?;
+    DONATOR_DONATIONS.save(deps.storage, (info.sender.clone(), donation_id), &Empty {})?;
+    Ok(Response::new()
+        .add_attribute("method", "disclose_donation")
+        .add_attribute("donation_id", donation_id.to_string())
+        .add_attribute("donator", info.sender))
+}
+
+// Checks the sender is the one who committed to an anonymous donation, the commitment can only
+// be opened once
+fn open_commitment(
+    donation: &mut Donation,
+    sender: &Addr,
+    salt: &str,
+) -> Result<(), ContractError> {
+    let commitment = match donation.commitment.take() {
+        Some(commitment) if donation.anonymous => commitment,
+        _ => return Err(ContractError::InvalidCommitment {}),
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(sender.as_bytes());
+    hasher.update(salt.as_bytes());
+    if hasher.finalize().as_slice() != commitment.as_slice() {
+        return Err(ContractError::InvalidCommitment {});
+    }
+    Ok(())
+}
+
+fn unregister_anonymous_donation(storage: &mut dyn Storage, funds: &[Coin]) -> StdResult<()> {
+    let mut anonymous_donations = ANONYMOUS_DONATIONS.load(storage)?;
+    for coin in funds {
+        if let Some(total) = anonymous_donations
+            .iter_mut()
+            .find(|total| total.denom == coin.denom)
+        {
+            total.amount = total.amount.saturating_sub(coin.amount);
+        }
+    }
+    anonymous_donations.retain(|total| !total.amount.is_zero());
+    ANONYMOUS_DONATIONS.save(storage, &anonymous_donations)
+}
+
+fn register_beneficiary_donation_infos(
+    storage: &mut dyn Storage,
+    beneficiary_addr: Addr,
+    mut donation_funds: Vec<Coin>,
+) -> StdResult<()> {
+    for coin in &donation_funds {
+        update_leaderboard(
+            storage,
+            &beneficiary_leaderboard(),
+            &beneficiary_addr,
+            coin,
+            false,
+        )?;
+    }
+    update_stats(storage, |stats| {
+        for coin in &donation_funds {
+            add_coin(&mut stats.paid_out, coin);
+        }
+    })?;
+    let _ = BENEFICIARIES.update(
+        storage,
+        beneficiary_addr,
+        |beneficiary_funds| -> Result<_, ContractError> {
+            if let Some(mut beneficiary_funds) = beneficiary_funds {
+                beneficiary_funds.append(&mut donation_funds);
+                Ok(beneficiary_funds)
+            } else {
+                Ok(donation_funds)
+            }
+        },
+    );
+    Ok(())
+}
+
+// Adds an amount to the lifetime total of an address, or takes it back
+fn update_leaderboard(
+    storage: &mut dyn Storage,
+    leaderboard: &Leaderboard,
+    address: &Addr,
+    coin: &Coin,
+    take_back: bool,
+) -> StdResult<()> {
+    let key = (address.clone(), coin.denom.clone());
+    let mut entry = leaderboard
+        .may_load(storage, key.clone())?
+        .unwrap_or(LeaderboardEntry {
+            address: address.clone(),
+            denom: coin.denom.clone(),
+            amount: Uint128::zero(),
+        });
+    entry.amount = if take_back {
+        entry.amount.saturating_sub(coin.amount)
+    } else {
+        entry.amount + coin.amount
+    };
+    if entry.amount.is_zero() {
+        return leaderboard.remove(storage, key);
+    }
+    leaderboard.save(storage, key, &entry)
+}
+
+// Recomputes both leaderboards from the donators / beneficiaries totals
+fn rebuild_leaderboards(storage: &mut dyn Storage) -> StdResult<()> {
+    let mut totals: Vec<(bool, Addr, Vec<Coin>)> = Vec::new();
+    for (is_donator, source) in [
+        (true, DONATORS),
+        (false, BENEFICIARIES),
+        (false, REMOVED_BENEFICIARIES),
+    ] {
+        for item in source.range(storage, None, None, Order::Ascending) {
+            let (address, funds) = item?;
+            let mut total: Vec<Coin> = Vec::new();
+            for coin in &funds {
+                add_coin(&mut total, coin);
+            }
+            totals.push((is_donator, address, total));
+        }
+    }
+    for (is_donator, address, total) in totals {
+        let leaderboard = if is_donator {
+            donator_leaderboard()
+        } else {
+            beneficiary_leaderboard()
+        };
+        for coin in total {
+            leaderboard.save(
+                storage,
+                (address.clone(), coin.denom.clone()),
+                &LeaderboardEntry {
+                    address: address.clone(),
+                    denom: coin.denom,
+                    amount: coin.amount,
+                },
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// Lifetime amount received by a beneficiary in the given denom
+fn received_in_denom(received_donations: &[Coin], denom: &str) -> Uint128 {
+    received_donations
+        .iter()
+        .filter(|coin| coin.denom == denom)
+        .map(|coin| coin.amount)
+        .sum()
+}
+
+// What a beneficiary can still receive in the given denom, None when it has no cap for it.
+// Denoms a beneficiary does not accept leave no room at all
+fn remaining_room(
+    storage: &dyn Storage,
+    beneficiary_addr: &Addr,
+    denom: &str,
+) -> StdResult<Option<Uint128>> {
+    if let Some(preference) = DENOM_PREFERENCES.may_load(storage, beneficiary_addr.clone())? {
+        if !preference.accepts(denom) {
+            return Ok(Some(Uint128::zero()));
+        }
+    }
+    let caps = CAPS
+        .may_load(storage, beneficiary_addr.clone())?
+        .unwrap_or_default();
+    match caps.iter().find(|cap| cap.denom == denom) {
+        Some(cap) => {
+            let received = BENEFICIARIES
+                .may_load(storage, beneficiary_addr.clone())?
+                .unwrap_or_default();
+            Ok(Some(
+                cap.amount
+                    .saturating_sub(received_in_denom(&received, denom)),
+            ))
+        }
+        None => Ok(None),
+    }
+}
+
+// Splits an amount equally, beneficiaries which remaining room is below the equal share are
+// filled up and their leftover share is split again amongst the others.
+// Also returns the amount nobody could receive because every beneficiary is full
+fn allocate(amount: Uint128, rooms: &[Option<Uint128>]) -> (Vec<Uint128>, Uint128) {
+    let mut allocation = vec![Uint128::zero(); rooms.len()];
+    let mut open: Vec<usize> = (0..rooms.len())
+        .filter(|i| rooms[*i] != Some(Uint128::zero()))
+        .collect();
+    let mut remaining = amount;
+    while !open.is_empty() && !remaining.is_zero() {
+        let share = remaining / Uint128::from(open.len() as u128);
+        let (full, others): (Vec<usize>, Vec<usize>) = open
+            .iter()
+            .partition(|i| matches!(rooms[**i], Some(room) if room <= share));
+        if full.is_empty() {
+            for i in &open {
+                allocation[*i] = share;
+            }
+            // The rounding dust stays in the contract
+            return (allocation, Uint128::zero());
+        }
+        for i in full {
+            let room = rooms[i].unwrap_or_default();
+            allocation[i] = room;
+            remaining -= room;
+        }
+        open = others;
+    }
+    (allocation, remaining)
+}
+
+// Fills each beneficiary up to its room in turn, starting with the first one
+fn allocate_in_order(amount: Uint128, rooms: &[Option<Uint128>]) -> (Vec<Uint128>, Uint128) {
+    let mut allocation = vec![Uint128::zero(); rooms.len()];
+    let mut remaining = amount;
+    for (share, room) in allocation.iter_mut().zip(rooms) {
+        *share = match room {
+            Some(room) => remaining.min(*room),
+            None => remaining,
+        };
+        remaining -= *share;
+    }
+    (allocation, remaining)
+}
+
+// splitmix64, good enough to spread consecutive seeds
+fn lottery_draw(block: &BlockInfo, donation_id: u64) -> u64 {
+    let mut z = block
+        .height
+        .wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        .wrapping_add(block.time.nanos())
+        .wrapping_add(donation_id.wrapping_mul(0xbf58_476d_1ce4_e5b9));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// Splits each denom of the funds amongst the active beneficiaries able to receive it
+fn compute_distribution(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    funds: &[Coin],
+) -> Result<SimulateDonationResponse, ContractError> {
+    // Beneficiaries which registration expired are skipped, frozen ones as well whatever the
+    // funds are (donations, pending pot, staking rewards or auction proceeds)
+    let mut beneficiaries_list = active_beneficiaries(storage, block)?;
+    if beneficiaries_list.is_empty() {
+        return Err(ContractError::NoBeneficiaries {});
+    }
+    let mut merged_funds: Vec<Coin> = Vec::new();
+    for coin in funds {
+        add_coin(&mut merged_funds, coin);
+    }
+
+    // Single recipient strategies start with the selected beneficiary, the next ones only
+    // receive what it can't (because of its cap or denom preference)
+    let strategy = STATE.load(storage)?.distribution_strategy;
+    let first = match strategy {
+        DistributionStrategy::Equal => 0,
+        DistributionStrategy::RoundRobin => match ROUND_ROBIN_CURSOR.may_load(storage)? {
+            Some(cursor) => beneficiaries_list
+                .iter()
+                .position(|beneficiary| *beneficiary > cursor)
+                .unwrap_or(0),
+            None => 0,
+        },
+        DistributionStrategy::Lottery => {
+            let donation_id = DONATION_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+            (lottery_draw(block, donation_id) % beneficiaries_list.len() as u64) as usize
+        }
+    };
+    beneficiaries_list.rotate_left(first);
+
+    let mut payouts: Vec<Payout> = beneficiaries_list
+        .into_iter()
+        .map(|beneficiary| Payout {
+            beneficiary,
+            amount: vec![],
+        })
+        .collect();
+    let mut refunded: Vec<Coin> = Vec::new();
+    for coin in merged_funds {
+        let rooms = payouts
+            .iter()
+            .map(|payout| remaining_room(storage, &payout.beneficiary, &coin.denom))
+            .collect::<StdResult<Vec<_>>>()?;
+        let (allocation, undistributed) = match strategy {
+            DistributionStrategy::Equal => allocate(coin.amount, &rooms),
+            _ => allocate_in_order(coin.amount, &rooms),
+        };
+        for (payout, amount) in payouts.iter_mut().zip(allocation) {
+            if !amount.is_zero() {
+                payout
+                    .amount
+                    .push(Coin::new(amount.u128(), coin.denom.clone()));
+            }
+        }
+        if !undistributed.is_zero() {
+            refunded.push(Coin::new(undistributed.u128(), coin.denom));
+        }
+    }
+    payouts.retain(|payout| !payout.amount.is_empty());
+    if payouts.is_empty() && !refunded.is_empty() {
+        return Err(ContractError::NoEligibleBeneficiaries {});
+    }
+    Ok(SimulateDonationResponse {
+        strategy,
+        payouts,
+        refunded,
+    })
+}
+
+pub fn set_distribution_strategy(
+    deps: DepsMut,
+    info: MessageInfo,
+    strategy: DistributionStrategy,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    STATE.update(deps.storage, |mut state| -> StdResult<_> {
+        state.distribution_strategy = strategy;
+        Ok(state)
+    })?;
+    Ok(Response::new()
+        .add_attribute("method", "set_distribution_strategy")
+        .add_attribute("strategy", format!("{:?}", strategy)))
+}
+
+// Pays the beneficiaries out of the contract balance, over IBC for remote beneficiaries
+fn pay_out(
+    storage: &mut dyn Storage,
+    env: &Env,
+    strategy: DistributionStrategy,
+    payouts: &[Payout],
+) -> StdResult<Vec<SubMsg>> {
+    if strategy == DistributionStrategy::RoundRobin {
+        if let Some(payout) = payouts.first() {
+            ROUND_ROBIN_CURSOR.save(storage, &payout.beneficiary)?;
+        }
+    }
+    let mut messages = Vec::new();
+    let mut transfer_queue = IBC_TRANSFER_QUEUE.may_load(storage)?.unwrap_or_default();
+    for payout in payouts {
+        match REMOTE_ACCOUNTS.may_load(storage, payout.beneficiary.clone())? {
+            Some(remote) => {
+                let timeout = IBC_TIMEOUT
+                    .may_load(storage)?
+                    .unwrap_or(DEFAULT_IBC_TIMEOUT);
+                // A transfer only carries one coin
+                for coin in &payout.amount {
+                    messages.push(SubMsg::reply_always(
+                        ibc_transfer_msg(
+                            &env.contract.address,
+                            &remote,
+                            coin,
+                            env.block.time.plus_seconds(timeout),
+                        ),
+                        IBC_TRANSFER_REPLY_ID,
+                    ));
+                    transfer_queue.push(IbcTransfer {
+                        beneficiary: payout.beneficiary.clone(),
+                        channel_id: remote.channel_id.clone(),
+                        amount: coin.clone(),
+                    });
+                }
+            }
+            // We are adding a new "BankMsg" for each beneficiary
+            None => messages.push(SubMsg::new(BankMsg::Send {
+                amount: payout.amount.clone(),
+                to_address: payout.beneficiary.clone().into_string(),
+            })),
+        }
+        register_beneficiary_donation_infos(
+            storage,
+            payout.beneficiary.clone(),
+            payout.amount.clone(),
+        )?;
+    }
+    IBC_TRANSFER_QUEUE.save(storage, &transfer_queue)?;
+    Ok(messages)
+}
+
+fn save_donation(storage: &mut dyn Storage, env: &Env, mut donation: Donation) -> StdResult<u64> {
+    let donation_id = DONATION_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    DONATION_COUNT.save(storage, &donation_id)?;
+    donation.id = donation_id;
+    if donation.anonymous {
+        donation.donator = Addr::unchecked(ANONYMOUS_DONATOR);
+    } else {
+        DONATOR_DONATIONS.save(storage, (donation.donator.clone(), donation_id), &Empty {})?;
+    }
+    DONATIONS.save(storage, donation_id, &donation)?;
+    // Donators which can't be paid back here (anonymous, remote or the pot itself) are not
+    // counted for refunds
+    let refundable = !donation.anonymous
+        && !is_remote_donator(&donation.donator)
+        && donation.donator != env.contract.address;
+    for payout in donation.payouts {
+        BENEFICIARY_DONATIONS.save(
+            storage,
+            (payout.beneficiary.clone(), donation_id),
+            &Empty {},
+        )?;
+        if refundable {
+            let key = (payout.beneficiary.clone(), donation.donator.clone());
+            let mut paid = BENEFICIARY_PAYERS
+                .may_load(storage, key.clone())?
+                .unwrap_or_default();
+            let mut total = BENEFICIARY_PAID
+                .may_load(storage, payout.beneficiary.clone())?
+                .unwrap_or_default();
+            for coin in &payout.amount {
+                add_coin(&mut paid, coin);
+                add_coin(&mut total, coin);
+            }
+            BENEFICIARY_PAYERS.save(storage, key, &paid)?;
+            BENEFICIARY_PAID.save(storage, payout.beneficiary, &total)?;
+        }
+    }
+    Ok(donation_id)
 }
 
-fn query_all_donators(deps: Deps) -> StdResult<DonatorListResponse> {
-    let donators = DONATORS.keys(deps.storage, None, None, cosmwasm_std::Order::Ascending);
-    let donators: Result<Vec<Addr>, _> = donators.collect();
-    return Ok(DonatorListResponse {
-        donators: donators?,
-    });
-}
+// Mints a receipt of the donation to the donator, when enabled
+fn mint_receipt(storage: &dyn Storage, env: &Env, donation_id: u64) -> StdResult<Option<WasmMsg>> {
+    let receipt_config = match RECEIPT_CONFIG.may_load(storage)? {
+        Some(receipt_config) => receipt_config,
+        None => return Ok(None),
+    };
+    let donation = DONATIONS.load(storage, donation_id)?;
+    // Remote donators can't hold a token on this chain, nor the pot a receipt for its royalties
+    if donation.anonymous
+        || is_remote_donator(&donation.donator)
+        || donation.donator == env.contract.address
+    {
+        return Ok(None);
+    }
+    // Accumulated / endowed donations are not paid out yet, count who they will go to
+    let beneficiaries = if donation.accumulated || donation.endowed {
+        active_beneficiaries(storage, &env.block)?.len()
+    } else {
+        donation.payouts.len()
+    };
+    let mint = Cw721ExecuteMsg::Mint {
+        token_id: donation_id.to_string(),
+        owner: donation.donator.to_string(),
+        token_uri: receipt_config.token_uri,
+        extension: ReceiptMetadata {
+            donation_id,
+            denoms: donation
+                .funds
+                .iter()
+                .map(|coin| coin.denom.clone())
+                .collect(),
+            amount: donation.funds,
+            timestamp: donation.time,
+            pot: env.contract.address.clone(),
+            beneficiaries: beneficiaries as u32,
+        },
+    };
+    Ok(Some(WasmMsg::Execute {
+        contract_addr: receipt_config.collection.to_string(),
+        msg: to_binary(&mint)?,
+        funds: vec![],
+    }))
+}
+
+pub fn set_receipt_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: Option<ReceiptConfig>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    match config {
+        Some(config) => {
+            deps.api.addr_validate(config.collection.as_str())?;
+            RECEIPT_CONFIG.save(deps.storage, &config)?;
+        }
+        None => RECEIPT_CONFIG.remove(deps.storage),
+    }
+    Ok(Response::new().add_attribute("method", "set_receipt_config"))
+}
+
+pub fn add_to_pot(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ibc_origin: Option<IbcOrigin>,
+    memo: Option<String>,
+    anonymous: Option<AnonymousDonation>,
+    min_output: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let options = DonationOptions {
+        memo: validate_memo(deps.storage, memo)?,
+        anonymous: anonymous.is_some(),
+        commitment: anonymous.and_then(|anonymous| anonymous.commitment),
+        min_output,
+    };
+    let mut funds: Vec<Coin> = Vec::new();
+    for coin in &info.funds {
+        add_coin(&mut funds, coin);
+    }
+    // The router cannot be trusted to bound its own output, the donator has to
+    if swap_config_for(deps.storage, &funds)?.is_some() && options.min_output.is_none() {
+        return Err(ContractError::MinOutputRequired {});
+    }
+    // Donations relayed by ibc-hooks are attributed to the sender on the other chain
+    let donator = match ibc_origin {
+        Some(origin) => remote_donator(&info.sender, &origin)?,
+        None if is_ibc_hooks_intermediary(deps.as_ref(), &info.sender) => {
+            return Err(ContractError::IbcOriginRequired {})
+        }
+        None => info.sender,
+    };
+    donate(deps, env, donator, funds, options)
+}
+
+// Empty memos are dropped, others have to fit the configured length
+fn validate_memo(
+    storage: &dyn Storage,
+    memo: Option<String>,
+) -> Result<Option<String>, ContractError> {
+    let memo = match memo {
+        Some(memo) if !memo.is_empty() => memo,
+        _ => return Ok(None),
+    };
+    match MEMO_CONFIG.may_load(storage)? {
+        Some(config) if memo.len() <= config.max_length as usize => Ok(Some(memo)),
+        _ => Err(ContractError::InvalidMemo {}),
+    }
+}
+
+pub fn set_memo_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: Option<MemoConfig>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    match config {
+        Some(config) => MEMO_CONFIG.save(deps.storage, &config)?,
+        None => MEMO_CONFIG.remove(deps.storage),
+    }
+    Ok(Response::new().add_attribute("method", "set_memo_config"))
+}
+
+// Shared by every way funds enter the pot
+fn donate(
+    deps: DepsMut,
+    env: Env,
+    donator: Addr,
+    funds: Vec<Coin>,
+    options: DonationOptions,
+) -> Result<Response, ContractError> {
+    // Donations are swapped into the target denom first, then handled in the reply. Donations
+    // made by the contract itself (auction proceeds, royalties) have no donator to bound the
+    // output, they are distributed as received
+    if let Some(swap_config) = swap_config_for(deps.storage, &funds)? {
+        if options.min_output.is_some() {
+            return swap_donation(deps, env, donator, funds, swap_config, options);
+        }
+    }
+    process_donation(deps, env, donator, funds, None, options)
+}
+
+// Swap config to use when some funds are not in the target denom
+fn swap_config_for(storage: &dyn Storage, funds: &[Coin]) -> StdResult<Option<SwapConfig>> {
+    Ok(SWAP_CONFIG
+        .may_load(storage)?
+        .filter(|config| funds.iter().any(|coin| coin.denom != config.target_denom)))
+}
+
+pub fn receive_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: Cw721ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // Anyone can deploy a collection calling this, only known ones are accepted
+    let collections = NFT_COLLECTIONS.may_load(deps.storage)?.unwrap_or_default();
+    if !collections.contains(&info.sender) {
+        return Err(ContractError::CollectionNotAllowed {});
+    }
+    let nft = HeldNft {
+        collection: info.sender,
+        token_id: msg.token_id,
+        sender: deps.api.addr_validate(&msg.sender)?,
+    };
+    let payload = if msg.msg.is_empty() {
+        ReceiveNftMsg::Hold {}
+    } else {
+        from_binary(&msg.msg)?
+    };
+    match payload {
+        ReceiveNftMsg::Hold {} => {
+            HELD_NFTS.save(
+                deps.storage,
+                (nft.collection.clone(), nft.token_id.clone()),
+                &nft,
+            )?;
+            Ok(Response::new()
+                .add_attribute("method", "receive_nft")
+                .add_attribute("collection", nft.collection)
+                .add_attribute("token_id", nft.token_id))
+        }
+        ReceiveNftMsg::Auction {
+            reserve_price,
+            duration,
+        } => create_auction(deps.storage, &env, nft, reserve_price, duration),
+    }
+}
+
+pub fn set_nft_collections(
+    deps: DepsMut,
+    info: MessageInfo,
+    collections: Vec<String>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    let collections = collections
+        .iter()
+        .map(|collection| deps.api.addr_validate(collection))
+        .collect::<StdResult<Vec<Addr>>>()?;
+    NFT_COLLECTIONS.save(deps.storage, &collections)?;
+    Ok(Response::new().add_attribute("method", "set_nft_collections"))
+}
+
+pub fn start_auction(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    reserve_price: Coin,
+    duration: u64,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    let key = (deps.api.addr_validate(&collection)?, token_id);
+    let nft = match HELD_NFTS.may_load(deps.storage, key.clone())? {
+        Some(nft) => nft,
+        None => return Err(ContractError::NftNotHeld {}),
+    };
+    HELD_NFTS.remove(deps.storage, key);
+    create_auction(deps.storage, &env, nft, reserve_price, duration)
+}
+
+fn create_auction(
+    storage: &mut dyn Storage,
+    env: &Env,
+    nft: HeldNft,
+    reserve_price: Coin,
+    duration: u64,
+) -> Result<Response, ContractError> {
+    if reserve_price.denom.is_empty() || duration == 0 {
+        return Err(ContractError::InvalidAuction {});
+    }
+    let id = AUCTION_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    AUCTION_COUNT.save(storage, &id)?;
+    let auction = Auction {
+        id,
+        nft,
+        reserve_price,
+        end: env.block.time.plus_seconds(duration),
+        highest_bid: None,
+        settled: false,
+    };
+    AUCTIONS.save(storage, id, &auction)?;
+    Ok(Response::new()
+        .add_attribute("method", "start_auction")
+        .add_attribute("auction_id", id.to_string())
+        .add_attribute("collection", auction.nft.collection)
+        .add_attribute("token_id", auction.nft.token_id))
+}
+
+pub fn bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    auction_id: u64,
+) -> Result<Response, ContractError> {
+    let mut auction = AUCTIONS.load(deps.storage, auction_id)?;
+    if auction.settled || env.block.time >= auction.end {
+        return Err(ContractError::AuctionNotActive {});
+    }
+    let amount = funds_in_denom(&info.funds, &auction.reserve_price.denom)?;
+    if amount < auction.reserve_price.amount
+        || amount.is_zero()
+        || matches!(&auction.highest_bid, Some(highest) if amount <= highest.amount.amount)
+    {
+        return Err(ContractError::BidTooLow {});
+    }
+
+    let mut response = Response::new();
+    let mut escrowed = ESCROWED_BIDS.may_load(deps.storage)?.unwrap_or_default();
+    // The previous highest bidder gets its escrow back
+    if let Some(outbid) = auction.highest_bid.take() {
+        sub_coin(&mut escrowed, &outbid.amount);
+        response = response.add_message(BankMsg::Send {
+            to_address: outbid.bidder.to_string(),
+            amount: vec![outbid.amount],
+        });
+    }
+    let bid = Coin {
+        denom: auction.reserve_price.denom.clone(),
+        amount,
+    };
+    add_coin(&mut escrowed, &bid);
+    ESCROWED_BIDS.save(deps.storage, &escrowed)?;
+    auction.highest_bid = Some(Bid {
+        bidder: info.sender.clone(),
+        amount: bid,
+    });
+    AUCTIONS.save(deps.storage, auction_id, &auction)?;
+    Ok(response
+        .add_attribute("method", "bid")
+        .add_attribute("auction_id", auction_id.to_string())
+        .add_attribute("bidder", info.sender)
+        .add_attribute("amount", amount))
+}
+
+// The NFT goes to the highest bidder and the proceeds are donated in the name of whoever sent it,
+// without any bid the NFT is held again
+pub fn settle_auction(deps: DepsMut, env: Env, auction_id: u64) -> Result<Response, ContractError> {
+    let mut auction = AUCTIONS.load(deps.storage, auction_id)?;
+    if auction.settled {
+        return Err(ContractError::AuctionNotActive {});
+    }
+    if env.block.time < auction.end {
+        return Err(ContractError::AuctionNotEnded {});
+    }
+    auction.settled = true;
+    AUCTIONS.save(deps.storage, auction_id, &auction)?;
+    if let Some(winning_bid) = &auction.highest_bid {
+        let mut escrowed = ESCROWED_BIDS.may_load(deps.storage)?.unwrap_or_default();
+        sub_coin(&mut escrowed, &winning_bid.amount);
+        ESCROWED_BIDS.save(deps.storage, &escrowed)?;
+    }
+
+    let nft = auction.nft;
+    let response = match auction.highest_bid {
+        Some(winning_bid) => donate(
+            deps,
+            env,
+            nft.sender,
+            vec![winning_bid.amount],
+            DonationOptions::default(),
+        )?
+        .add_message(WasmMsg::Execute {
+            contract_addr: nft.collection.to_string(),
+            msg: to_binary(&Cw721ExecuteMsg::TransferNft {
+                recipient: winning_bid.bidder.to_string(),
+                token_id: nft.token_id.clone(),
+            })?,
+            funds: vec![],
+        })
+        .add_attribute("winner", winning_bid.bidder),
+        None => {
+            HELD_NFTS.save(
+                deps.storage,
+                (nft.collection.clone(), nft.token_id.clone()),
+                &nft,
+            )?;
+            Response::new()
+        }
+    };
+    Ok(response
+        .add_attribute("method", "settle_auction")
+        .add_attribute("auction_id", auction_id.to_string()))
+}
+
+// Royalties are usually paid with a plain bank transfer, whatever the contract holds on top of
+// what it owes is donated in its own name
+pub fn distribute_royalties(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let unaccounted = unaccounted_balance(deps.as_ref(), &env)?.unaccounted;
+    // A balance no beneficiary would be paid from is left to Sweep
+    match compute_distribution(deps.storage, &env.block, &unaccounted) {
+        Ok(simulation) if !simulation.payouts.is_empty() => {}
+        Ok(_) | Err(ContractError::NoEligibleBeneficiaries {}) => {
+            return Err(ContractError::NothingToDistribute {})
+        }
+        Err(err) => return Err(err),
+    }
+    let donator = env.contract.address.clone();
+    donate(deps, env, donator, unaccounted, DonationOptions::default())
+}
+
+// Only moves what the contract holds on top of what it owes and the beneficiaries can't receive,
+// the rest is left to DistributeRoyalties
+pub fn sweep(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    to: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    let to_addr = deps.api.addr_validate(&to)?;
+    let swept = match unaccounted_balance(deps.as_ref(), &env)?
+        .sweepable
+        .into_iter()
+        .find(|coin| coin.denom == denom)
+    {
+        Some(swept) => swept,
+        None => return Err(ContractError::NothingToSweep {}),
+    };
+    update_stats(deps.storage, |stats| sub_coin(&mut stats.dust, &swept))?;
+    Ok(Response::new()
+        .add_attribute("method", "sweep")
+        .add_attribute("to", to_addr.clone())
+        .add_attribute("amount", swept.to_string())
+        .add_message(BankMsg::Send {
+            to_address: to_addr.into_string(),
+            amount: vec![swept],
+        }))
+}
+
+// Compares the bank balance with what the state says the contract holds
+fn unaccounted_balance(deps: Deps, env: &Env) -> StdResult<UnaccountedBalanceResponse> {
+    let balance = deps.querier.query_all_balances(&env.contract.address)?;
+    let held = held_funds(deps.storage)?;
+    let mut unaccounted: Vec<Coin> = Vec::new();
+    for coin in &balance {
+        let owed = held
+            .iter()
+            .find(|held| held.denom == coin.denom)
+            .map(|held| held.amount)
+            .unwrap_or_default();
+        add_coin(
+            &mut unaccounted,
+            &Coin {
+                denom: coin.denom.clone(),
+                amount: coin.amount.saturating_sub(owed),
+            },
+        );
+    }
+    // What a donation of the unaccounted balance would refund can't go to any beneficiary
+    let mut sweepable = match compute_distribution(deps.storage, &env.block, &unaccounted) {
+        Ok(simulation) => simulation.refunded,
+        Err(ContractError::NoBeneficiaries {}) => unaccounted.clone(),
+        Err(err) => return Err(StdError::generic_err(err.to_string())),
+    };
+    for coin in STATS.may_load(deps.storage)?.unwrap_or_default().dust {
+        add_coin(&mut sweepable, &coin);
+    }
+    Ok(UnaccountedBalanceResponse {
+        balance,
+        held,
+        unaccounted,
+        sweepable,
+    })
+}
+
+// Funds the contract holds on behalf of someone
+fn held_funds(storage: &dyn Storage) -> StdResult<Vec<Coin>> {
+    let mut held: Vec<Coin> = Vec::new();
+    for bond in BONDS.range(storage, None, None, Order::Ascending) {
+        add_coin(&mut held, &bond?.1);
+    }
+    for claimable in CLAIMABLE.range(storage, None, None, Order::Ascending) {
+        for coin in claimable?.1 {
+            add_coin(&mut held, &coin);
+        }
+    }
+    let fees = COLLECTED_FEES.may_load(storage)?.unwrap_or_default();
+    let pending_pot = PENDING_POT.may_load(storage)?.unwrap_or_default();
+    let pending_refunds = PENDING_REFUNDS.may_load(storage)?.unwrap_or_default();
+    let escrowed_bids = ESCROWED_BIDS.may_load(storage)?.unwrap_or_default();
+    // Already counted, it would otherwise be donated again and again
+    let dust = STATS.may_load(storage)?.unwrap_or_default().dust;
+    // Rewards withdrawn by the distribution module and undelegated principal belong to the
+    // endowment, until harvested or delegated again
+    let ledger = ENDOWMENT_LEDGER.may_load(storage)?.unwrap_or_default();
+    for coin in fees
+        .iter()
+        .chain(pending_pot.iter())
+        .chain(pending_refunds.iter())
+        .chain(escrowed_bids.iter())
+        .chain(dust.iter())
+        .chain(ledger.withdrawn_rewards.iter())
+        .chain(ledger.undelegated.iter())
+    {
+        add_coin(&mut held, coin);
+    }
+    for round in ROUNDS.range(storage, None, None, Order::Ascending) {
+        let round = round?.1;
+        let owed = if round.finalized {
+            round.leftover - round.reclaimed
+        } else {
+            round.matching_pool
+        };
+        add_coin(&mut held, &Coin::new(owed.u128(), round.denom));
+    }
+    Ok(held)
+}
+
+// Address ibc-hooks executes the contract with for a transfer received on the given channel,
+// see DeriveIntermediateSender in the ibc-hooks module
+fn ibc_hooks_sender(channel: &str, original_sender: &str, prefix: &str) -> StdResult<String> {
+    let type_hash = Sha256::digest(IBC_HOOKS_SENDER_PREFIX.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(type_hash);
+    hasher.update(format!("{}/{}", channel, original_sender).as_bytes());
+    bech32::encode(prefix, hasher.finalize().to_base32(), Variant::Bech32)
+        .map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+// Checks the donation really comes from ibc-hooks for this origin
+fn remote_donator(sender: &Addr, origin: &IbcOrigin) -> Result<Addr, ContractError> {
+    let prefix = match sender.as_str().rsplit_once('1') {
+        Some((prefix, _)) => prefix,
+        None => return Err(ContractError::InvalidIbcOrigin {}),
+    };
+    if ibc_hooks_sender(&origin.channel, &origin.sender, prefix)? != sender.as_str() {
+        return Err(ContractError::InvalidIbcOrigin {});
+    }
+    Ok(Addr::unchecked(format!(
+        "{}{}/{}",
+        REMOTE_DONATOR_PREFIX, origin.channel, origin.sender
+    )))
+}
+
+// ibc-hooks intermediaries are the only 32 bytes addresses which are not contracts, apart from
+// interchain accounts
+fn is_ibc_hooks_intermediary(deps: Deps, sender: &Addr) -> bool {
+    let is_hashed = matches!(
+        bech32::decode(sender.as_str()),
+        Ok((_, data, _)) if Vec::<u8>::from_base32(&data).is_ok_and(|bytes| bytes.len() == 32)
+    );
+    is_hashed
+        && deps
+            .querier
+            .query::<ContractInfoResponse>(
+                &WasmQuery::ContractInfo {
+                    contract_addr: sender.to_string(),
+                }
+                .into(),
+            )
+            .is_err()
+}
+
+fn is_remote_donator(donator: &Addr) -> bool {
+    donator.as_str().starts_with(REMOTE_DONATOR_PREFIX)
+}
+
+fn process_donation(
+    deps: DepsMut,
+    env: Env,
+    donator: Addr,
+    funds: Vec<Coin>,
+    swap: Option<SwapRecord>,
+    options: DonationOptions,
+) -> Result<Response, ContractError> {
+    // Nothing is recorded, let alone a receipt minted, for a donation without funds
+    if funds.is_empty() {
+        return Err(ContractError::InvalidFunds {});
+    }
+    // In endowment mode donations are staked, only the rewards are distributed
+    if let Some(endowment) = ENDOWMENT_CONFIG.may_load(deps.storage)? {
+        return endow_donation(deps, env, donator, funds, swap, options, endowment);
+    }
+    // In accumulation mode donations wait in the pending pot until the next epoch
+    if EPOCH_CONFIG.may_load(deps.storage)?.is_some() {
+        return add_to_pending_pot(deps, env, donator, funds, swap, options);
+    }
+
+    let SimulateDonationResponse {
+        strategy,
+        payouts,
+        refunded,
+    } = compute_distribution(deps.storage, &env.block, &funds)?;
+
+    // The donator is only accounted for what has not been refunded
+    let mut donated = funds.clone();
+    for coin in donated.iter_mut() {
+        if let Some(refund) = refunded.iter().find(|refund| refund.denom == coin.denom) {
+            coin.amount -= refund.amount;
+        }
+    }
+    donated.retain(|coin| !coin.amount.is_zero());
+    register_donator(
+        deps.storage,
+        &env,
+        donator.clone(),
+        options.anonymous,
+        donated,
+    )?;
+    record_dust(deps.storage, &funds, &payouts, &refunded)?;
+
+    // Building a new Response
+    let mut response: Response<Empty> = Response::new();
+
+    // Distributing money amongst beneficiaries
+    response = response.add_submessages(pay_out(deps.storage, &env, strategy, &payouts)?);
+    // What could not be distributed goes back to the donator
+    if !refunded.is_empty() {
+        // Failing lets ibc-hooks refund the transfer on the other chain
+        if is_remote_donator(&donator) {
+            return Err(ContractError::NoEligibleBeneficiaries {});
+        }
+        response = response.add_attribute("refunded", format!("{:?}", refunded));
+        // Undistributed royalties simply stay in the contract
+        if donator != env.contract.address {
+            response = response.add_message(BankMsg::Send {
+                to_address: donator.to_string(),
+                amount: refunded.clone(),
+            });
+        }
+    }
+
+    let beneficiaries_list: Vec<Addr> = payouts
+        .iter()
+        .map(|payout| payout.beneficiary.clone())
+        .collect();
+    let donation_id = save_donation(
+        deps.storage,
+        &env,
+        Donation {
+            id: 0,
+            donator,
+            height: env.block.height,
+            time: env.block.time,
+            funds,
+            accumulated: false,
+            endowed: false,
+            strategy,
+            payouts,
+            refunded,
+            swap,
+            memo: options.memo.clone(),
+            anonymous: options.anonymous,
+            commitment: options.commitment,
+            retracted: false,
+        },
+    )?;
+
+    response = response.add_messages(mint_receipt(deps.storage, &env, donation_id)?);
+
+    let beneficiaries_as_str = format!("{:?}", beneficiaries_list);
+    response = response.add_attribute("donation_id", donation_id.to_string());
+    if let Some(memo) = options.memo {
+        response = response.add_attribute("memo", memo);
+    }
+    response = response.add_attribute("strategy", format!("{:?}", strategy));
+    response = response.add_attribute("beneficiaries", beneficiaries_as_str);
+    response = response.add_attribute(
+        "amount_of_beneficiaries",
+        beneficiaries_list.len().to_string(),
+    );
+    Ok(response.add_attribute("method", "add_to_pot"))
+}
+
+fn add_to_pending_pot(
+    deps: DepsMut,
+    env: Env,
+    donator: Addr,
+    funds: Vec<Coin>,
+    swap: Option<SwapRecord>,
+    options: DonationOptions,
+) -> Result<Response, ContractError> {
+    let mut pending_pot = PENDING_POT.may_load(deps.storage)?.unwrap_or_default();
+    for coin in &funds {
+        add_coin(&mut pending_pot, coin);
+    }
+    PENDING_POT.save(deps.storage, &pending_pot)?;
+    register_donator(
+        deps.storage,
+        &env,
+        donator.clone(),
+        options.anonymous,
+        funds.clone(),
+    )?;
+    let strategy = STATE.load(deps.storage)?.distribution_strategy;
+    let donation_id = save_donation(
+        deps.storage,
+        &env,
+        Donation {
+            id: 0,
+            donator,
+            height: env.block.height,
+            time: env.block.time,
+            funds,
+            accumulated: true,
+            endowed: false,
+            strategy,
+            payouts: vec![],
+            refunded: vec![],
+            swap,
+            memo: options.memo.clone(),
+            anonymous: options.anonymous,
+            commitment: options.commitment,
+            retracted: false,
+        },
+    )?;
+    Ok(Response::new()
+        .add_messages(mint_receipt(deps.storage, &env, donation_id)?)
+        .add_attributes(options.memo.map(|memo| ("memo", memo)))
+        .add_attribute("method", "add_to_pot")
+        .add_attribute("donation_id", donation_id.to_string())
+        .add_attribute("accumulated", "true"))
+}
+
+// Sends every coin not in the target denom to the router, with a minimum output derived
+// from the router simulation and the maximum slippage. The total output is checked against
+// the donator minimum once every swap is done
+fn swap_donation(
+    deps: DepsMut,
+    env: Env,
+    donator: Addr,
+    funds: Vec<Coin>,
+    swap_config: SwapConfig,
+    options: DonationOptions,
+) -> Result<Response, ContractError> {
+    if PENDING_SWAP.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::SwapInProgress {});
+    }
+    let (donated, offered): (Vec<Coin>, Vec<Coin>) = funds
+        .into_iter()
+        .partition(|coin| coin.denom == swap_config.target_denom);
+
+    let mut response = Response::new();
+    for offer in &offered {
+        let simulation: SimulateSwapResponse = deps.querier.query_wasm_smart(
+            swap_config.router.clone(),
+            &SwapRouterQueryMsg::SimulateSwap {
+                offer: offer.clone(),
+                ask_denom: swap_config.target_denom.clone(),
+            },
+        )?;
+        let min_output = simulation.amount * (Decimal::one() - swap_config.max_slippage);
+        response = response.add_submessage(SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: swap_config.router.to_string(),
+                msg: to_binary(&SwapRouterExecuteMsg::Swap {
+                    ask_denom: swap_config.target_denom.clone(),
+                    min_output,
+                })?,
+                funds: vec![offer.clone()],
+            },
+            SWAP_REPLY_ID,
+        ));
+    }
+
+    // The donated funds are already part of the balance
+    let balance_before = deps
+        .querier
+        .query_balance(env.contract.address, swap_config.target_denom)?
+        .amount;
+    PENDING_SWAP.save(
+        deps.storage,
+        &PendingSwap {
+            donator,
+            remaining: offered.len() as u32,
+            offered,
+            donated: donated.first().map(|coin| coin.amount).unwrap_or_default(),
+            balance_before,
+            options,
+        },
+    )?;
+    Ok(response.add_attribute("method", "swap_donation"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        SWAP_REPLY_ID => swap_reply(deps, env),
+        IBC_TRANSFER_REPLY_ID => ibc_transfer_reply(deps, msg.result),
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+// Once every swap is done, the output is distributed like a donation in the target denom
+fn swap_reply(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let mut pending_swap = PENDING_SWAP.load(deps.storage)?;
+    pending_swap.remaining -= 1;
+    if pending_swap.remaining > 0 {
+        PENDING_SWAP.save(deps.storage, &pending_swap)?;
+        return Ok(Response::new());
+    }
+    PENDING_SWAP.remove(deps.storage);
+
+    let target_denom = SWAP_CONFIG.load(deps.storage)?.target_denom;
+    let balance = deps
+        .querier
+        .query_balance(env.contract.address.clone(), target_denom.clone())?
+        .amount;
+    let received = balance
+        .checked_sub(pending_swap.balance_before)
+        .map_err(StdError::from)?;
+    if received < pending_swap.options.min_output.unwrap_or_default() {
+        return Err(ContractError::SwapOutputTooLow {});
+    }
+    let total = pending_swap.donated + received;
+    process_donation(
+        deps,
+        env,
+        pending_swap.donator,
+        coins(total.u128(), target_denom.clone()),
+        Some(SwapRecord {
+            offered: pending_swap.offered,
+            received: Coin::new(received.u128(), target_denom),
+        }),
+        pending_swap.options,
+    )
+}
+
+pub fn set_swap_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: Option<SwapConfig>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    match config {
+        Some(config) => {
+            deps.api.addr_validate(config.router.as_str())?;
+            if config.target_denom.is_empty() || config.max_slippage > Decimal::one() {
+                return Err(ContractError::InvalidSwapConfig {});
+            }
+            SWAP_CONFIG.save(deps.storage, &config)?;
+        }
+        None => SWAP_CONFIG.remove(deps.storage),
+    }
+    Ok(Response::new().add_attribute("method", "set_swap_config"))
+}
+
+// Delegates a donation in the staking denom, split evenly across the configured validators
+fn endow_donation(
+    deps: DepsMut,
+    env: Env,
+    donator: Addr,
+    funds: Vec<Coin>,
+    swap: Option<SwapRecord>,
+    options: DonationOptions,
+    endowment: EndowmentConfig,
+) -> Result<Response, ContractError> {
+    let amount = funds_in_denom(&funds, &endowment.denom)?;
+    if amount.is_zero() {
+        return Err(ContractError::InvalidFunds {});
+    }
+    let validators_count = Uint128::from(endowment.validators.len() as u128);
+    let share = amount / validators_count;
+    // The rounding remainder goes to the first validator
+    let mut first_share = amount - share * validators_count;
+
+    let mut response = Response::new();
+    let mut ledger = ENDOWMENT_LEDGER.may_load(deps.storage)?.unwrap_or_default();
+    for validator in &endowment.validators {
+        let delegated = share + first_share;
+        first_share = Uint128::zero();
+        if delegated.is_zero() {
+            continue;
+        }
+        record_withdrawn_rewards(deps.as_ref(), &env, &mut ledger, validator)?;
+        response = response.add_message(StakingMsg::Delegate {
+            validator: validator.clone(),
+            amount: Coin::new(delegated.u128(), endowment.denom.clone()),
+        });
+    }
+    ENDOWMENT_LEDGER.save(deps.storage, &ledger)?;
+
+    register_donator(
+        deps.storage,
+        &env,
+        donator.clone(),
+        options.anonymous,
+        funds.clone(),
+    )?;
+    let strategy = STATE.load(deps.storage)?.distribution_strategy;
+    let donation_id = save_donation(
+        deps.storage,
+        &env,
+        Donation {
+            id: 0,
+            donator,
+            height: env.block.height,
+            time: env.block.time,
+            funds,
+            accumulated: false,
+            endowed: true,
+            strategy,
+            payouts: vec![],
+            refunded: vec![],
+            swap,
+            memo: options.memo.clone(),
+            anonymous: options.anonymous,
+            commitment: options.commitment,
+            retracted: false,
+        },
+    )?;
+    Ok(response
+        .add_messages(mint_receipt(deps.storage, &env, donation_id)?)
+        .add_attributes(options.memo.map(|memo| ("memo", memo)))
+        .add_attribute("method", "add_to_pot")
+        .add_attribute("donation_id", donation_id.to_string())
+        .add_attribute("endowed", amount))
+}
+
+pub fn set_endowment_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: Option<EndowmentConfig>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    match config {
+        Some(config) => {
+            let validators = deps.querier.query_all_validators()?;
+            if config.validators.is_empty()
+                || config.denom != deps.querier.query_bonded_denom()?
+                || config
+                    .validators
+                    .iter()
+                    .any(|validator| !validators.iter().any(|known| known.address == *validator))
+            {
+                return Err(ContractError::InvalidEndowmentConfig {});
+            }
+            ENDOWMENT_CONFIG.save(deps.storage, &config)?;
+        }
+        // Existing delegations are kept, they can still be undelegated
+        None => ENDOWMENT_CONFIG.remove(deps.storage),
+    }
+    Ok(Response::new().add_attribute("method", "set_endowment_config"))
+}
+
+// Withdraws the staking rewards and splits them amongst the beneficiaries, along with the
+// rewards withdrawn by earlier delegation changes. Rewards the beneficiaries can't receive are
+// left in the contract
+pub fn harvest_rewards(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let mut ledger = ENDOWMENT_LEDGER.may_load(deps.storage)?.unwrap_or_default();
+    let mut response = Response::new();
+    for delegation in deps
+        .querier
+        .query_all_delegations(env.contract.address.clone())?
+    {
+        let before = ledger.withdrawn_rewards.clone();
+        record_withdrawn_rewards(deps.as_ref(), &env, &mut ledger, &delegation.validator)?;
+        if ledger.withdrawn_rewards != before {
+            response = response.add_message(DistributionMsg::WithdrawDelegatorReward {
+                validator: delegation.validator,
+            });
+        }
+    }
+    let rewards = std::mem::take(&mut ledger.withdrawn_rewards);
+    if rewards.is_empty() {
+        return Err(ContractError::NothingToDistribute {});
+    }
+    ENDOWMENT_LEDGER.save(deps.storage, &ledger)?;
+
+    // Bank messages are executed after the withdrawals
+    let SimulateDonationResponse {
+        strategy,
+        payouts,
+        refunded,
+    } = compute_distribution(deps.storage, &env.block, &rewards)?;
+    record_dust(deps.storage, &rewards, &payouts, &refunded)?;
+    Ok(response
+        .add_submessages(pay_out(deps.storage, &env, strategy, &payouts)?)
+        .add_attribute("method", "harvest_rewards")
+        .add_attribute("rewards", format!("{:?}", rewards))
+        .add_attribute("strategy", format!("{:?}", strategy)))
+}
+
+// Any change to a delegation, and withdrawing its rewards, moves the rewards accumulated so far
+// to the contract balance
+fn record_withdrawn_rewards(
+    deps: Deps,
+    env: &Env,
+    ledger: &mut EndowmentLedger,
+    validator: &str,
+) -> StdResult<()> {
+    let accumulated_rewards = deps
+        .querier
+        .query_delegation(env.contract.address.clone(), validator)?
+        .map(|full_delegation| full_delegation.accumulated_rewards)
+        .unwrap_or_default();
+    for coin in &accumulated_rewards {
+        add_coin(&mut ledger.withdrawn_rewards, coin);
+    }
+    Ok(())
+}
+
+// Delegations can only be made to the configured validators
+fn assert_configured_validator(
+    storage: &dyn Storage,
+    validator: &str,
+) -> Result<(), ContractError> {
+    match ENDOWMENT_CONFIG.may_load(storage)? {
+        Some(config) if config.validators.iter().any(|v| v == validator) => Ok(()),
+        _ => Err(ContractError::InvalidValidator {}),
+    }
+}
+
+// Delegations are only moved away from configured validators, or from the ones taken out of the
+// config since
+fn assert_delegated_validator(deps: Deps, env: &Env, validator: &str) -> Result<(), ContractError> {
+    if assert_configured_validator(deps.storage, validator).is_ok()
+        || deps
+            .querier
+            .query_delegation(env.contract.address.clone(), validator)?
+            .is_some()
+    {
+        return Ok(());
+    }
+    Err(ContractError::InvalidValidator {})
+}
+
+pub fn delegate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    validator: String,
+    amount: Coin,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    assert_configured_validator(deps.storage, &validator)?;
+    let mut ledger = ENDOWMENT_LEDGER.may_load(deps.storage)?.unwrap_or_default();
+    match ledger
+        .undelegated
+        .iter_mut()
+        .find(|coin| coin.denom == amount.denom)
+    {
+        Some(undelegated) if undelegated.amount >= amount.amount && !amount.amount.is_zero() => {
+            undelegated.amount -= amount.amount
+        }
+        _ => return Err(ContractError::InvalidFunds {}),
+    }
+    ledger.undelegated.retain(|coin| !coin.amount.is_zero());
+    record_withdrawn_rewards(deps.as_ref(), &env, &mut ledger, &validator)?;
+    ENDOWMENT_LEDGER.save(deps.storage, &ledger)?;
+    Ok(Response::new()
+        .add_message(StakingMsg::Delegate { validator, amount })
+        .add_attribute("method", "delegate"))
+}
+
+pub fn redelegate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    src_validator: String,
+    dst_validator: String,
+    amount: Coin,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    assert_delegated_validator(deps.as_ref(), &env, &src_validator)?;
+    assert_configured_validator(deps.storage, &dst_validator)?;
+    let mut ledger = ENDOWMENT_LEDGER.may_load(deps.storage)?.unwrap_or_default();
+    record_withdrawn_rewards(deps.as_ref(), &env, &mut ledger, &src_validator)?;
+    record_withdrawn_rewards(deps.as_ref(), &env, &mut ledger, &dst_validator)?;
+    ENDOWMENT_LEDGER.save(deps.storage, &ledger)?;
+    Ok(Response::new()
+        .add_message(StakingMsg::Redelegate {
+            src_validator,
+            dst_validator,
+            amount,
+        })
+        .add_attribute("method", "redelegate"))
+}
+
+// Undelegated funds come back to the contract balance once unbonded, they are kept as principal
+// until delegated again
+pub fn undelegate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    validator: String,
+    amount: Coin,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    assert_delegated_validator(deps.as_ref(), &env, &validator)?;
+    let mut ledger = ENDOWMENT_LEDGER.may_load(deps.storage)?.unwrap_or_default();
+    record_withdrawn_rewards(deps.as_ref(), &env, &mut ledger, &validator)?;
+    add_coin(&mut ledger.undelegated, &amount);
+    ENDOWMENT_LEDGER.save(deps.storage, &ledger)?;
+    Ok(Response::new()
+        .add_message(StakingMsg::Undelegate { validator, amount })
+        .add_attribute("method", "undelegate"))
+}
+
+pub fn set_remote_account(
+    deps: DepsMut,
+    info: MessageInfo,
+    beneficiary: Option<String>,
+    remote: Option<RemoteAccount>,
+) -> Result<Response, ContractError> {
+    let beneficiary_addr = match beneficiary {
+        Some(beneficiary) => deps.api.addr_validate(&beneficiary)?,
+        None => info.sender.clone(),
+    };
+    if info.sender != beneficiary_addr
+        && !has_role(deps.storage, &info.sender, Role::BeneficiaryManager)?
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !BENEFICIARIES.has(deps.storage, beneficiary_addr.clone())
+        && !REMOVED_BENEFICIARIES.has(deps.storage, beneficiary_addr.clone())
+    {
+        return Err(ContractError::NotABeneficiary {});
+    }
+    match remote {
+        Some(remote) => {
+            if remote.channel_id.is_empty() || remote.address.is_empty() {
+                return Err(ContractError::InvalidRemoteAccount {});
+            }
+            REMOTE_ACCOUNTS.save(deps.storage, beneficiary_addr.clone(), &remote)?;
+        }
+        None => REMOTE_ACCOUNTS.remove(deps.storage, beneficiary_addr.clone()),
+    }
+    Ok(Response::new()
+        .add_attribute("method", "set_remote_account")
+        .add_attribute("beneficiary", beneficiary_addr))
+}
+
+pub fn set_ibc_timeout(
+    deps: DepsMut,
+    info: MessageInfo,
+    seconds: u64,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    if seconds == 0 {
+        return Err(ContractError::InvalidIbcTimeout {});
+    }
+    IBC_TIMEOUT.save(deps.storage, &seconds)?;
+    Ok(Response::new()
+        .add_attribute("method", "set_ibc_timeout")
+        .add_attribute("seconds", seconds.to_string()))
+}
+
+// IbcMsg::Transfer has no memo, the MsgTransfer is encoded by hand so that ibc-hooks calls
+// the contract back (through sudo) once the packet is acknowledged or timed out
+fn ibc_transfer_msg(
+    contract: &Addr,
+    remote: &RemoteAccount,
+    amount: &Coin,
+    timeout: Timestamp,
+) -> CosmosMsg {
+    let mut token = Vec::new();
+    encode_bytes(&mut token, 1, amount.denom.as_bytes());
+    encode_bytes(&mut token, 2, amount.amount.to_string().as_bytes());
+
+    let memo = format!(r#"{{"ibc_callback":"{}"}}"#, contract);
+    let mut value = Vec::new();
+    encode_bytes(&mut value, 1, b"transfer");
+    encode_bytes(&mut value, 2, remote.channel_id.as_bytes());
+    encode_bytes(&mut value, 3, &token);
+    encode_bytes(&mut value, 4, contract.as_bytes());
+    encode_bytes(&mut value, 5, remote.address.as_bytes());
+    // Field 6 (timeout_height) is left empty, only the timestamp is used
+    value.push(7 << 3);
+    encode_varint(&mut value, timeout.nanos());
+    encode_bytes(&mut value, 8, memo.as_bytes());
+    CosmosMsg::Stargate {
+        type_url: IBC_TRANSFER_TYPE_URL.to_string(),
+        value: Binary::from(value),
+    }
+}
+
+// Length delimited protobuf field
+fn encode_bytes(buf: &mut Vec<u8>, field: u8, bytes: &[u8]) {
+    buf.push(field << 3 | 2);
+    encode_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn encode_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+// Keeps track of the packet sent by a transfer, or credits the beneficiary right away
+// when the transfer could not be sent
+fn ibc_transfer_reply(deps: DepsMut, result: SubMsgResult) -> Result<Response, ContractError> {
+    let mut transfer_queue = IBC_TRANSFER_QUEUE.load(deps.storage)?;
+    if transfer_queue.is_empty() {
+        return Err(ContractError::UnknownReplyId {
+            id: IBC_TRANSFER_REPLY_ID,
+        });
+    }
+    let transfer = transfer_queue.remove(0);
+    IBC_TRANSFER_QUEUE.save(deps.storage, &transfer_queue)?;
+
+    match result {
+        SubMsgResult::Ok(response) => {
+            // The sequence is in the MsgTransferResponse, or else in the send_packet event
+            let sequence = response
+                .data
+                .as_ref()
+                .and_then(parse_transfer_sequence)
+                .or_else(|| {
+                    response
+                        .events
+                        .iter()
+                        .filter(|event| event.ty == "send_packet")
+                        .flat_map(|event| event.attributes.iter())
+                        .find(|attribute| attribute.key == "packet_sequence")
+                        .and_then(|attribute| attribute.value.parse().ok())
+                });
+            match sequence {
+                Some(sequence) => {
+                    IBC_TRANSFERS.save(deps.storage, (&transfer.channel_id, sequence), &transfer)?
+                }
+                None => {
+                    let mut unsequenced = UNSEQUENCED_TRANSFERS
+                        .may_load(deps.storage, &transfer.channel_id)?
+                        .unwrap_or_default();
+                    unsequenced.push(transfer.clone());
+                    UNSEQUENCED_TRANSFERS.save(deps.storage, &transfer.channel_id, &unsequenced)?;
+                }
+            }
+            Ok(Response::new().add_attribute("method", "ibc_transfer_sent"))
+        }
+        SubMsgResult::Err(error) => {
+            credit_claimable(deps.storage, &transfer)?;
+            Ok(Response::new()
+                .add_attribute("method", "ibc_transfer_failed")
+                .add_attribute("error", error))
+        }
+    }
+}
+
+// MsgTransferResponse only holds the sequence, as its first (varint) field
+fn parse_transfer_sequence(data: &Binary) -> Option<u64> {
+    let bytes = data.as_slice();
+    if bytes.first() != Some(&0x08) {
+        return None;
+    }
+    let mut sequence: u64 = 0;
+    for (i, byte) in bytes[1..].iter().take(10).enumerate() {
+        sequence |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some(sequence);
+        }
+    }
+    None
+}
+
+// Credited to the current address of the beneficiary, which may have changed since the transfer
+fn credit_claimable(storage: &mut dyn Storage, transfer: &IbcTransfer) -> StdResult<()> {
+    let beneficiary = resolve_beneficiary(storage, &BENEFICIARIES, transfer.beneficiary.clone())?;
+    let mut claimable = CLAIMABLE
+        .may_load(storage, beneficiary.clone())?
+        .unwrap_or_default();
+    add_coin(&mut claimable, &transfer.amount);
+    CLAIMABLE.save(storage, beneficiary, &claimable)
+}
+
+// Packet lifecycle callbacks, as delivered by the ibc-hooks module
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    let (channel, sequence, success) = match msg {
+        SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcAck {
+            channel,
+            sequence,
+            success,
+            ..
+        }) => (channel, sequence, success),
+        SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcTimeout { channel, sequence }) => {
+            (channel, sequence, false)
+        }
+    };
+    let transfer = match IBC_TRANSFERS.may_load(deps.storage, (&channel, sequence))? {
+        Some(transfer) => {
+            IBC_TRANSFERS.remove(deps.storage, (&channel, sequence));
+            transfer
+        }
+        // Only our transfers carry the callback memo, an unknown sequence belongs to the oldest
+        // transfer sent on the channel without its sequence
+        None => {
+            let mut unsequenced = UNSEQUENCED_TRANSFERS
+                .may_load(deps.storage, &channel)?
+                .unwrap_or_default();
+            if unsequenced.is_empty() {
+                return Ok(Response::new().add_attribute("method", "ibc_lifecycle_complete"));
+            }
+            let transfer = unsequenced.remove(0);
+            if unsequenced.is_empty() {
+                UNSEQUENCED_TRANSFERS.remove(deps.storage, &channel);
+            } else {
+                UNSEQUENCED_TRANSFERS.save(deps.storage, &channel, &unsequenced)?;
+            }
+            transfer
+        }
+    };
+    if !success {
+        credit_claimable(deps.storage, &transfer)?;
+    }
+    Ok(Response::new()
+        .add_attribute("method", "ibc_lifecycle_complete")
+        .add_attribute("beneficiary", transfer.beneficiary)
+        .add_attribute("success", success.to_string()))
+}
+
+// Sends what failed to reach a remote beneficiary to its local address
+pub fn claim_payouts(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    if FROZEN_BENEFICIARIES.has(deps.storage, info.sender.clone()) {
+        return Err(ContractError::BeneficiaryFrozen {});
+    }
+    let claimable = CLAIMABLE
+        .may_load(deps.storage, info.sender.clone())?
+        .unwrap_or_default();
+    if claimable.is_empty() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    CLAIMABLE.remove(deps.storage, info.sender.clone());
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: claimable,
+        })
+        .add_attribute("method", "claim_payouts"))
+}
+
+pub fn freeze_beneficiary(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    beneficiary: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    let beneficiary_addr = deps.api.addr_validate(&beneficiary)?;
+    if !BENEFICIARIES.has(deps.storage, beneficiary_addr.clone())
+        && !REMOVED_BENEFICIARIES.has(deps.storage, beneficiary_addr.clone())
+    {
+        return Err(ContractError::NotABeneficiary {});
+    }
+    if FROZEN_BENEFICIARIES.has(deps.storage, beneficiary_addr.clone()) {
+        return Err(ContractError::BeneficiaryFrozen {});
+    }
+    let dispute_period = DISPUTE_PERIOD
+        .may_load(deps.storage)?
+        .unwrap_or(DEFAULT_DISPUTE_PERIOD);
+    let freeze_id = FREEZE_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    FREEZE_COUNT.save(deps.storage, &freeze_id)?;
+    let held = CLAIMABLE
+        .may_load(deps.storage, beneficiary_addr.clone())?
+        .unwrap_or_default();
+    FREEZES.save(
+        deps.storage,
+        freeze_id,
+        &Freeze {
+            id: freeze_id,
+            beneficiary: beneficiary_addr.clone(),
+            reason,
+            frozen_at: env.block.time,
+            dispute_end: env.block.time.plus_seconds(dispute_period),
+            held,
+            resolution: None,
+            resolved_at: None,
+            settled: vec![],
+            refunds: None,
+        },
+    )?;
+    FROZEN_BENEFICIARIES.save(deps.storage, beneficiary_addr.clone(), &freeze_id)?;
+    Ok(Response::new()
+        .add_attribute("method", "freeze_beneficiary")
+        .add_attribute("freeze_id", freeze_id.to_string())
+        .add_attribute("beneficiary", beneficiary_addr))
+}
+
+// A release is possible at any time, anything else has to wait for the end of the dispute period
+pub fn resolve_freeze(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    freeze_id: u64,
+    resolution: FreezeResolution,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    let mut freeze = FREEZES.load(deps.storage, freeze_id)?;
+    if freeze.resolution.is_some() {
+        return Err(ContractError::FreezeResolved {});
+    }
+    if resolution != FreezeResolution::Release && env.block.time < freeze.dispute_end {
+        return Err(ContractError::DisputeNotOver {});
+    }
+    FROZEN_BENEFICIARIES.remove(deps.storage, freeze.beneficiary.clone());
+    let claimable = CLAIMABLE
+        .may_load(deps.storage, freeze.beneficiary.clone())?
+        .unwrap_or_default();
+
+    let mut response = Response::new();
+    if resolution != FreezeResolution::Release {
+        CLAIMABLE.remove(deps.storage, freeze.beneficiary.clone());
+        // The bond of a fraudulent beneficiary is kept as a fee
+        if BENEFICIARIES.has(deps.storage, freeze.beneficiary.clone()) {
+            move_beneficiary_to_removed(
+                deps.storage,
+                env.block.height,
+                freeze.beneficiary.clone(),
+            )?;
+            EXPIRATIONS.remove(deps.storage, freeze.beneficiary.clone());
+        }
+        release_bond(deps.storage, &freeze.beneficiary, false)?;
+    }
+    match resolution {
+        FreezeResolution::Release => {}
+        // Whatever can't be distributed stays in the contract
+        FreezeResolution::Redistribute if !claimable.is_empty() => {
+            let SimulateDonationResponse {
+                strategy,
+                payouts,
+                refunded,
+            } = compute_distribution(deps.storage, &env.block, &claimable)?;
+            record_dust(deps.storage, &claimable, &payouts, &refunded)?;
+            response = response.add_submessages(pay_out(deps.storage, &env, strategy, &payouts)?);
+        }
+        FreezeResolution::Redistribute => {}
+        // The balance is held until every donator is refunded with RefundDonators
+        FreezeResolution::ReturnToDonators => {
+            let mut pending_refunds = PENDING_REFUNDS.may_load(deps.storage)?.unwrap_or_default();
+            for coin in &claimable {
+                add_coin(&mut pending_refunds, coin);
+            }
+            PENDING_REFUNDS.save(deps.storage, &pending_refunds)?;
+            freeze.refunds = Some(DonatorRefunds {
+                paid: BENEFICIARY_PAID
+                    .may_load(deps.storage, freeze.beneficiary.clone())?
+                    .unwrap_or_default(),
+                refunded: vec![],
+                last_donator: None,
+                done: false,
+            });
+        }
+    }
+
+    freeze.resolution = Some(resolution);
+    freeze.resolved_at = Some(env.block.time);
+    freeze.settled = claimable;
+    FREEZES.save(deps.storage, freeze_id, &freeze)?;
+    Ok(response
+        .add_attribute("method", "resolve_freeze")
+        .add_attribute("freeze_id", freeze_id.to_string())
+        .add_attribute("resolution", format!("{:?}", resolution)))
+}
+
+// Permissionless, a page at a time so that any amount of donators can be refunded
+pub fn refund_donators(
+    deps: DepsMut,
+    freeze_id: u64,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit
+        .unwrap_or(DEFAULT_REFUNDS_LIMIT)
+        .min(MAX_REFUNDS_LIMIT) as usize;
+    let mut freeze = FREEZES.load(deps.storage, freeze_id)?;
+    if !matches!(&freeze.refunds, Some(refunds) if !refunds.done) {
+        return Err(ContractError::NothingToRefund {});
+    }
+    let refunds = refund_page(deps.storage, &mut freeze, limit)?;
+    FREEZES.save(deps.storage, freeze_id, &freeze)?;
+    Ok(Response::new()
+        .add_messages(refunds)
+        .add_attribute("method", "refund_donators")
+        .add_attribute("freeze_id", freeze_id.to_string()))
+}
+
+// Refunds the settled balance of a freeze to the next donators who paid the beneficiary
+// directly, in proportion of what they paid in each denom
+fn refund_page(
+    storage: &mut dyn Storage,
+    freeze: &mut Freeze,
+    limit: usize,
+) -> StdResult<Vec<BankMsg>> {
+    let refunds = match freeze.refunds.as_mut() {
+        Some(refunds) if !refunds.done => refunds,
+        _ => return Ok(vec![]),
+    };
+    let start = refunds.last_donator.clone().map(Bound::exclusive);
+    let payers = BENEFICIARY_PAYERS
+        .prefix(freeze.beneficiary.clone())
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<(Addr, Vec<Coin>)>>>()?;
+
+    let mut messages = Vec::new();
+    let mut released: Vec<Coin> = Vec::new();
+    for (donator, donator_paid) in &payers {
+        let mut refund: Vec<Coin> = Vec::new();
+        for coin in &freeze.settled {
+            let amount_of = |coins: &[Coin]| {
+                coins
+                    .iter()
+                    .find(|other| other.denom == coin.denom)
+                    .map(|other| other.amount)
+                    .unwrap_or_default()
+            };
+            let own = amount_of(donator_paid);
+            if own.is_zero() {
+                continue;
+            }
+            // Never more than what is left, whatever was paid since the resolution
+            let left = coin.amount.saturating_sub(amount_of(&refunds.refunded));
+            let amount = coin
+                .amount
+                .multiply_ratio(own, amount_of(&refunds.paid).max(own))
+                .min(left);
+            add_coin(
+                &mut refund,
+                &Coin {
+                    denom: coin.denom.clone(),
+                    amount,
+                },
+            );
+        }
+        for coin in &refund {
+            add_coin(&mut refunds.refunded, coin);
+            add_coin(&mut released, coin);
+        }
+        if !refund.is_empty() {
+            messages.push(BankMsg::Send {
+                to_address: donator.to_string(),
+                amount: refund,
+            });
+        }
+    }
+    if let Some((donator, _)) = payers.last() {
+        refunds.last_donator = Some(donator.clone());
+    }
+    if payers.len() < limit {
+        refunds.done = true;
+        // The rounding dust is no longer held for anyone
+        let mut dust: Vec<Coin> = Vec::new();
+        for coin in &freeze.settled {
+            let refunded = refunds
+                .refunded
+                .iter()
+                .find(|refunded| refunded.denom == coin.denom)
+                .map(|refunded| refunded.amount)
+                .unwrap_or_default();
+            add_coin(
+                &mut dust,
+                &Coin {
+                    denom: coin.denom.clone(),
+                    amount: coin.amount.saturating_sub(refunded),
+                },
+            );
+        }
+        for coin in &dust {
+            add_coin(&mut released, coin);
+        }
+        update_stats(storage, |stats| {
+            for coin in &dust {
+                add_coin(&mut stats.dust, coin);
+            }
+        })?;
+    }
+
+    let mut pending_refunds = PENDING_REFUNDS.may_load(storage)?.unwrap_or_default();
+    for coin in &released {
+        if let Some(pending) = pending_refunds
+            .iter_mut()
+            .find(|pending| pending.denom == coin.denom)
+        {
+            pending.amount = pending.amount.saturating_sub(coin.amount);
+        }
+    }
+    pending_refunds.retain(|coin| !coin.amount.is_zero());
+    PENDING_REFUNDS.save(storage, &pending_refunds)?;
+    Ok(messages)
+}
+
+pub fn set_dispute_period(
+    deps: DepsMut,
+    info: MessageInfo,
+    seconds: u64,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    DISPUTE_PERIOD.save(deps.storage, &seconds)?;
+    Ok(Response::new()
+        .add_attribute("method", "set_dispute_period")
+        .add_attribute("seconds", seconds.to_string()))
+}
+
+pub fn set_grace_period(
+    deps: DepsMut,
+    info: MessageInfo,
+    seconds: Option<u64>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    match seconds {
+        Some(seconds) => GRACE_PERIOD.save(deps.storage, &seconds)?,
+        None => GRACE_PERIOD.remove(deps.storage),
+    }
+    Ok(Response::new().add_attribute("method", "set_grace_period"))
+}
+
+// Sends an accumulated donation back from the pending pot, as long as no epoch distributed it
+pub fn retract_donation(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    donation_id: u64,
+    salt: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut donation = DONATIONS.load(deps.storage, donation_id)?;
+    // Anonymous donators prove they made the donation by opening its commitment
+    match (donation.anonymous, salt) {
+        (true, Some(salt)) => open_commitment(&mut donation, &info.sender, &salt)?,
+        (false, None) if donation.donator == info.sender => {}
+        _ => return Err(ContractError::Unauthorized {}),
+    }
+    let grace_period = GRACE_PERIOD.may_load(deps.storage)?;
+    let last_distributed = match EPOCH_COUNT.may_load(deps.storage)? {
+        Some(epoch_id) => EPOCHS.load(deps.storage, epoch_id)?.last_donation_id,
+        None => 0,
+    };
+    match grace_period {
+        Some(seconds)
+            if donation.accumulated
+                && !donation.retracted
+                && donation_id > last_distributed
+                && env.block.time < donation.time.plus_seconds(seconds) => {}
+        _ => return Err(ContractError::NotRetractable {}),
+    }
+
+    let mut pending_pot = PENDING_POT.load(deps.storage)?;
+    for coin in &donation.funds {
+        match pending_pot
+            .iter_mut()
+            .find(|pending| pending.denom == coin.denom)
+        {
+            Some(pending) => {
+                pending.amount = pending
+                    .amount
+                    .checked_sub(coin.amount)
+                    .map_err(StdError::from)?
+            }
+            None => return Err(ContractError::NotRetractable {}),
+        }
+    }
+    pending_pot.retain(|coin| !coin.amount.is_zero());
+    PENDING_POT.save(deps.storage, &pending_pot)?;
+    if donation.anonymous {
+        unregister_anonymous_donation(deps.storage, &donation.funds)?;
+    } else {
+        unregister_donation_infos(
+            deps.storage,
+            env.block.height,
+            info.sender.clone(),
+            &donation.funds,
+        )?;
+    }
+    update_stats(deps.storage, |stats| {
+        for coin in &donation.funds {
+            if let Some(donated) = stats
+                .donated
+                .iter_mut()
+                .find(|donated| donated.denom == coin.denom)
+            {
+                donated.amount = donated.amount.saturating_sub(coin.amount);
+            }
+        }
+        stats.donated.retain(|coin| !coin.amount.is_zero());
+    })?;
+    donation.retracted = true;
+    DONATIONS.save(deps.storage, donation_id, &donation)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: donation.funds,
+        })
+        .add_attribute("method", "retract_donation")
+        .add_attribute("donation_id", donation_id.to_string()))
+}
+
+pub fn set_epoch_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: Option<EpochConfig>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    // Whatever is still pending can be distributed after accumulation is turned off
+    match config {
+        Some(config) => EPOCH_CONFIG.save(deps.storage, &config)?,
+        None => EPOCH_CONFIG.remove(deps.storage),
+    }
+    Ok(Response::new().add_attribute("method", "set_epoch_config"))
+}
+
+// Splits the pending pot amongst the current beneficiaries, at most once per epoch
+pub fn distribute(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    if let Some(next_distribution) = NEXT_DISTRIBUTION.may_load(deps.storage)? {
+        if !next_distribution.is_expired(&env.block) {
+            return Err(ContractError::EpochNotEnded {});
+        }
+    }
+    let pending_pot = PENDING_POT.may_load(deps.storage)?.unwrap_or_default();
+    if pending_pot.is_empty() {
+        return Err(ContractError::NothingToDistribute {});
+    }
+
+    let SimulateDonationResponse {
+        strategy, payouts, ..
+    } = compute_distribution(deps.storage, &env.block, &pending_pot)?;
+    let messages = pay_out(deps.storage, &env, strategy, &payouts)?;
+
+    // Refused amounts and rounding dust stay in the pending pot
+    let mut carried_over = pending_pot;
+    for coin in payouts.iter().flat_map(|payout| payout.amount.iter()) {
+        if let Some(pending) = carried_over.iter_mut().find(|c| c.denom == coin.denom) {
+            pending.amount -= coin.amount;
+        }
+    }
+    carried_over.retain(|coin| !coin.amount.is_zero());
+    PENDING_POT.save(deps.storage, &carried_over)?;
+
+    if let Some(config) = EPOCH_CONFIG.may_load(deps.storage)? {
+        NEXT_DISTRIBUTION.save(deps.storage, &config.duration.after(&env.block))?;
+    }
+    let epoch_id = EPOCH_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    EPOCH_COUNT.save(deps.storage, &epoch_id)?;
+    let last_donation_id = DONATION_COUNT.may_load(deps.storage)?.unwrap_or_default();
+    EPOCHS.save(
+        deps.storage,
+        epoch_id,
+        &Epoch {
+            id: epoch_id,
+            height: env.block.height,
+            time: env.block.time,
+            strategy,
+            payouts,
+            carried_over,
+            last_donation_id,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("method", "distribute")
+        .add_attribute("epoch_id", epoch_id.to_string())
+        .add_attribute("strategy", format!("{:?}", strategy)))
+}
+
+// Returns the total amount sent in the given denom, any other denom is rejected
+fn funds_in_denom(funds: &[Coin], denom: &str) -> Result<Uint128, ContractError> {
+    if funds.iter().any(|coin| coin.denom != denom) {
+        return Err(ContractError::InvalidFunds {});
+    }
+    Ok(funds.iter().map(|coin| coin.amount).sum())
+}
+
+fn append_coins(
+    storage: &mut dyn Storage,
+    target: &Map<(u64, Addr), Vec<Coin>>,
+    key: (u64, Addr),
+    mut funds: Vec<Coin>,
+) -> StdResult<Vec<Coin>> {
+    target.update(storage, key, |existing| -> StdResult<_> {
+        let mut existing = existing.unwrap_or_default();
+        existing.append(&mut funds);
+        Ok(existing)
+    })
+}
+
+pub fn create_round(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    start: Timestamp,
+    end: Timestamp,
+    denom: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    if end <= start || end <= env.block.time {
+        return Err(ContractError::InvalidRoundWindow {});
+    }
+    // Funds sent along with the round creation are used as the initial matching pool
+    let matching_pool = funds_in_denom(&info.funds, &denom)?;
+    let round_id = ROUND_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    ROUND_COUNT.save(deps.storage, &round_id)?;
+    ROUNDS.save(
+        deps.storage,
+        round_id,
+        &Round {
+            id: round_id,
+            start,
+            end,
+            denom,
+            matching_pool,
+            finalized: false,
+            leftover: Uint128::zero(),
+            reclaimed: Uint128::zero(),
+        },
+    )?;
+    if !matching_pool.is_zero() {
+        ROUND_FUNDERS.save(deps.storage, (round_id, info.sender), &matching_pool)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "create_round")
+        .add_attribute("round_id", round_id.to_string())
+        .add_attribute("matching_pool", matching_pool))
+}
+
+pub fn fund_round(
+    deps: DepsMut,
+    info: MessageInfo,
+    round_id: u64,
+) -> Result<Response, ContractError> {
+    let mut round = ROUNDS.load(deps.storage, round_id)?;
+    if round.finalized {
+        return Err(ContractError::RoundAlreadyFinalized {});
+    }
+    let amount = funds_in_denom(&info.funds, &round.denom)?;
+    if amount.is_zero() {
+        return Err(ContractError::InvalidFunds {});
+    }
+    round.matching_pool += amount;
+    ROUNDS.save(deps.storage, round_id, &round)?;
+    ROUND_FUNDERS.update(
+        deps.storage,
+        (round_id, info.sender),
+        |funded| -> StdResult<_> { Ok(funded.unwrap_or_default() + amount) },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "fund_round")
+        .add_attribute("round_id", round_id.to_string())
+        .add_attribute("matching_pool", round.matching_pool))
+}
+
+pub fn contribute_to_round(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    round_id: u64,
+    beneficiary: String,
+) -> Result<Response, ContractError> {
+    let round = ROUNDS.load(deps.storage, round_id)?;
+    if round.finalized || env.block.time < round.start || env.block.time >= round.end {
+        return Err(ContractError::RoundNotActive {});
+    }
+    let beneficiary_addr = deps.api.addr_validate(&beneficiary)?;
+    if !BENEFICIARIES.has(deps.storage, beneficiary_addr.clone()) {
+        return Err(ContractError::NotABeneficiary {});
+    }
+    if !is_active(deps.storage, &env.block, &beneficiary_addr)? {
+        return Err(ContractError::RegistrationExpired {});
+    }
+    if FROZEN_BENEFICIARIES.has(deps.storage, beneficiary_addr.clone()) {
+        return Err(ContractError::BeneficiaryFrozen {});
+    }
+    let amount = funds_in_denom(&info.funds, &round.denom)?;
+    if amount.is_zero() {
+        return Err(ContractError::InvalidFunds {});
+    }
+
+    ROUND_CONTRIBUTIONS.update(
+        deps.storage,
+        (round_id, beneficiary_addr.clone(), info.sender.clone()),
+        |contribution| -> StdResult<_> { Ok(contribution.unwrap_or_default() + amount) },
+    )?;
+    let contribution = coins(amount.u128(), round.denom);
+    append_coins(
+        deps.storage,
+        &ROUND_DONATORS,
+        (round_id, info.sender.clone()),
+        contribution.clone(),
+    )?;
+    append_coins(
+        deps.storage,
+        &ROUND_BENEFICIARIES,
+        (round_id, beneficiary_addr.clone()),
+        contribution.clone(),
+    )?;
+
+    // Contributions go straight to the beneficiary, only the match is paid at the end of the round
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: beneficiary_addr.to_string(),
+            amount: contribution,
+        })
+        .add_attribute("method", "contribute_to_round")
+        .add_attribute("round_id", round_id.to_string())
+        .add_attribute("beneficiary", beneficiary_addr)
+        .add_attribute("amount", amount))
+}
+
+pub fn finalize_round(deps: DepsMut, env: Env, round_id: u64) -> Result<Response, ContractError> {
+    let mut round = ROUNDS.load(deps.storage, round_id)?;
+    if round.finalized {
+        return Err(ContractError::RoundAlreadyFinalized {});
+    }
+    if env.block.time < round.end {
+        return Err(ContractError::RoundNotEnded {});
+    }
+
+    let mut response = Response::new();
+    let mut matched = Uint128::zero();
+    for round_match in compute_round_matches(deps.storage, &round)? {
+        if round_match.projected_match.is_zero() {
+            continue;
+        }
+        matched += round_match.projected_match;
+        let match_funds = coins(round_match.projected_match.u128(), round.denom.clone());
+        append_coins(
+            deps.storage,
+            &ROUND_BENEFICIARIES,
+            (round_id, round_match.beneficiary.clone()),
+            match_funds.clone(),
+        )?;
+        // The match of a frozen beneficiary is held with its other balances until the freeze is
+        // resolved
+        if FROZEN_BENEFICIARIES.has(deps.storage, round_match.beneficiary.clone()) {
+            let mut claimable = CLAIMABLE
+                .may_load(deps.storage, round_match.beneficiary.clone())?
+                .unwrap_or_default();
+            add_coin(&mut claimable, &match_funds[0]);
+            CLAIMABLE.save(deps.storage, round_match.beneficiary, &claimable)?;
+            continue;
+        }
+        response = response.add_message(BankMsg::Send {
+            to_address: round_match.beneficiary.into_string(),
+            amount: match_funds,
+        });
+    }
+    // The rounding remainder, or the whole pool without any contribution, goes back to the
+    // funders
+    round.leftover = round.matching_pool - matched;
+    round.finalized = true;
+    ROUNDS.save(deps.storage, round_id, &round)?;
+
+    Ok(response
+        .add_attribute("method", "finalize_round")
+        .add_attribute("round_id", round_id.to_string())
+        .add_attribute("leftover", round.leftover))
+}
+
+// Each funder gets the leftover in proportion to what it put in the matching pool
+pub fn reclaim_matching_pool(
+    deps: DepsMut,
+    info: MessageInfo,
+    round_id: u64,
+) -> Result<Response, ContractError> {
+    let mut round = ROUNDS.load(deps.storage, round_id)?;
+    if !round.finalized {
+        return Err(ContractError::RoundNotFinalized {});
+    }
+    let funded = match ROUND_FUNDERS.may_load(deps.storage, (round_id, info.sender.clone()))? {
+        Some(funded) => funded,
+        None => return Err(ContractError::NothingToReclaim {}),
+    };
+    let share = round.leftover.multiply_ratio(funded, round.matching_pool);
+    if share.is_zero() {
+        return Err(ContractError::NothingToReclaim {});
+    }
+    ROUND_FUNDERS.remove(deps.storage, (round_id, info.sender.clone()));
+    round.reclaimed += share;
+    ROUNDS.save(deps.storage, round_id, &round)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(share.u128(), round.denom),
+        })
+        .add_attribute("method", "reclaim_matching_pool")
+        .add_attribute("round_id", round_id.to_string())
+        .add_attribute("amount", share))
+}
+
+// Splits the matching pool proportionally to the square of the sum of the square roots
+// of the contributions received by each beneficiary
+fn compute_round_matches(storage: &dyn Storage, round: &Round) -> StdResult<Vec<RoundMatch>> {
+    let mut sums: Vec<(Addr, Uint128, Uint256)> = Vec::new();
+    for contribution in
+        ROUND_CONTRIBUTIONS
+            .sub_prefix(round.id)
+            .range(storage, None, None, Order::Ascending)
+    {
+        let ((beneficiary, _donator), amount) = contribution?;
+        let root = (Uint256::from(amount) * Uint256::from(QF_PRECISION)).isqrt();
+        match sums.last_mut() {
+            Some((last, total, sum_of_roots)) if *last == beneficiary => {
+                *total += amount;
+                *sum_of_roots += root;
+            }
+            _ => sums.push((beneficiary, amount, root)),
+        }
+    }
+
+    let squares: Vec<Uint256> = sums.iter().map(|(_, _, sum)| *sum * *sum).collect();
+    let total_squares = squares
+        .iter()
+        .fold(Uint256::zero(), |total, square| total + *square);
+
+    let mut matches = Vec::new();
+    for ((beneficiary, contributions, _), square) in sums.into_iter().zip(squares) {
+        let projected_match = if total_squares.is_zero() {
+            Uint128::zero()
+        } else {
+            (Uint256::from(round.matching_pool) * square / total_squares).try_into()?
+        };
+        matches.push(RoundMatch {
+            beneficiary,
+            contributions,
+            projected_match,
+        });
+    }
+    Ok(matches)
+}
+
+pub fn set_governance_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    config: Option<GovernanceConfig>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    match config {
+        Some(config) => {
+            let one = Decimal::one();
+            if config.quorum.is_zero()
+                || config.quorum > one
+                || config.threshold.is_zero()
+                || config.threshold > one
+                || config.voting_period == 0
+            {
+                return Err(ContractError::InvalidGovernanceConfig {});
+            }
+            let enabling = GOVERNANCE.may_load(deps.storage)?.is_none();
+            GOVERNANCE.save(deps.storage, &config)?;
+            if enabling {
+                rebuild_voting_weights(deps.storage, env.block.height)?;
+            }
+        }
+        None => GOVERNANCE.remove(deps.storage),
+    }
+    Ok(Response::new().add_attribute("method", "set_governance_config"))
+}
+
+// Storage key of a voter set, donators are weighted separately in each denom
+fn voter_set_key(voters: &VoterSet) -> String {
+    match voters {
+        VoterSet::Beneficiaries => "beneficiaries".to_string(),
+        VoterSet::Donators { denom } => format!("donators/{}", denom),
+    }
+}
+
+// Adds an amount to the voting weight of an address and to the total of its voter set,
+// or takes it back. Weights are only kept while governance is enabled
+fn update_voting_weight(
+    storage: &mut dyn Storage,
+    voters: &VoterSet,
+    voter: &Addr,
+    amount: Uint128,
+    take_back: bool,
+    height: u64,
+) -> StdResult<()> {
+    if GOVERNANCE.may_load(storage)?.is_none() {
+        return Ok(());
+    }
+    let voters = voter_set_key(voters);
+    let key = (voters.as_str(), voter.clone());
+    let weight = VOTING_WEIGHTS
+        .may_load(storage, key.clone())?
+        .unwrap_or_default();
+    let total = TOTAL_VOTING_WEIGHTS
+        .may_load(storage, &voters)?
+        .unwrap_or_default();
+    let (weight, total) = if take_back {
+        let taken = amount.min(weight);
+        (weight - taken, total.saturating_sub(taken))
+    } else {
+        (weight + amount, total + amount)
+    };
+    if weight.is_zero() {
+        VOTING_WEIGHTS.remove(storage, key, height)?;
+    } else {
+        VOTING_WEIGHTS.save(storage, key, &weight, height)?;
+    }
+    TOTAL_VOTING_WEIGHTS.save(storage, &voters, &total, height)
+}
+
+// Remote donators can't vote from this chain, so they are not counted in the total either
+fn update_donator_voting_weight(
+    storage: &mut dyn Storage,
+    donator: &Addr,
+    coin: &Coin,
+    take_back: bool,
+    height: u64,
+) -> StdResult<()> {
+    if is_remote_donator(donator) {
+        return Ok(());
+    }
+    let voters = VoterSet::Donators {
+        denom: coin.denom.clone(),
+    };
+    update_voting_weight(storage, &voters, donator, coin.amount, take_back, height)
+}
+
+// Voting weights are computed from scratch when governance is enabled, weights left from an
+// earlier period with governance are stale
+fn rebuild_voting_weights(storage: &mut dyn Storage, height: u64) -> StdResult<()> {
+    let weights: Vec<(String, Addr)> = VOTING_WEIGHTS
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for (voters, voter) in weights {
+        VOTING_WEIGHTS.remove(storage, (voters.as_str(), voter), height)?;
+    }
+    let totals: Vec<String> = TOTAL_VOTING_WEIGHTS
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for voters in totals {
+        TOTAL_VOTING_WEIGHTS.remove(storage, &voters, height)?;
+    }
+
+    let beneficiaries: Vec<Addr> = BENEFICIARIES
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for beneficiary in beneficiaries {
+        let voters = VoterSet::Beneficiaries;
+        update_voting_weight(
+            storage,
+            &voters,
+            &beneficiary,
+            Uint128::from(1u8),
+            false,
+            height,
+        )?;
+    }
+    let donators: Vec<(Addr, Vec<Coin>)> = DONATORS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for (donator, funds) in donators {
+        for coin in &funds {
+            update_donator_voting_weight(storage, &donator, coin, false, height)?;
+        }
+    }
+    Ok(())
+}
+
+// Weight as of the start of the given block
+fn voting_weight(
+    storage: &dyn Storage,
+    voters: &VoterSet,
+    voter: &Addr,
+    height: u64,
+) -> StdResult<Uint128> {
+    let voters = voter_set_key(voters);
+    Ok(VOTING_WEIGHTS
+        .may_load_at_height(storage, (voters.as_str(), voter.clone()), height)?
+        .unwrap_or_default())
+}
+
+fn total_voting_weight(
+    storage: &dyn Storage,
+    voters: &VoterSet,
+    height: u64,
+) -> StdResult<Uint128> {
+    Ok(TOTAL_VOTING_WEIGHTS
+        .may_load_at_height(storage, &voter_set_key(voters), height)?
+        .unwrap_or_default())
+}
+
+pub fn propose(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    action: ProposalAction,
+) -> Result<Response, ContractError> {
+    let config = GOVERNANCE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::GovernanceDisabled {})?;
+    // Weights are frozen as of the start of the block, later donations or registrations
+    // don't count for this proposal
+    let height = env.block.height;
+    if voting_weight(deps.storage, &config.voters, &info.sender, height)?.is_zero() {
+        return Err(ContractError::NotAVoter {});
+    }
+    // Fail early on proposals that could never be executed
+    match &action {
+        ProposalAction::RegisterBeneficiary { beneficiary } => {
+            let beneficiary_addr = deps.api.addr_validate(beneficiary)?;
+            if BENEFICIARIES.has(deps.storage, beneficiary_addr) {
+                return Err(ContractError::AlreadyABeneficiary {});
+            }
+        }
+        ProposalAction::RemoveBeneficiary { beneficiary } => {
+            let beneficiary_addr = deps.api.addr_validate(beneficiary)?;
+            if !BENEFICIARIES.has(deps.storage, beneficiary_addr) {
+                return Err(ContractError::NotABeneficiary {});
+            }
+        }
+        ProposalAction::SetBeneficiaryCaps { beneficiary, caps } => {
+            validate_caps(deps.storage, &deps.api.addr_validate(beneficiary)?, caps)?
+        }
+    }
+
+    let proposal_id = PROPOSAL_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    PROPOSAL_COUNT.save(deps.storage, &proposal_id)?;
+    let proposal = Proposal {
+        id: proposal_id,
+        proposer: info.sender,
+        action,
+        voting_ends: env.block.time.plus_seconds(config.voting_period),
+        total_weight: total_voting_weight(deps.storage, &config.voters, height)?,
+        voters: config.voters,
+        height,
+        quorum: config.quorum,
+        threshold: config.threshold,
+        yes: Uint128::zero(),
+        no: Uint128::zero(),
+        status: ProposalStatus::Open,
+    };
+    PROPOSALS.save(deps.storage, proposal_id, &proposal)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "propose")
+        .add_attribute("proposal_id", proposal_id.to_string()))
+}
+
+pub fn vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    approve: bool,
+) -> Result<Response, ContractError> {
+    if GOVERNANCE.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::GovernanceDisabled {});
+    }
+    let mut proposal = PROPOSALS.load(deps.storage, proposal_id)?;
+    if proposal.status != ProposalStatus::Open || env.block.time >= proposal.voting_ends {
+        return Err(ContractError::VotingClosed {});
+    }
+    if VOTES.has(deps.storage, (proposal_id, info.sender.clone())) {
+        return Err(ContractError::AlreadyVoted {});
+    }
+    let weight = voting_weight(
+        deps.storage,
+        &proposal.voters,
+        &info.sender,
+        proposal.height,
+    )?;
+    if weight.is_zero() {
+        return Err(ContractError::NotAVoter {});
+    }
+    VOTES.save(deps.storage, (proposal_id, info.sender.clone()), &approve)?;
+    match approve {
+        true => proposal.yes += weight,
+        false => proposal.no += weight,
+    }
+    PROPOSALS.save(deps.storage, proposal_id, &proposal)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "vote")
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("voter", info.sender)
+        .add_attribute("weight", weight))
+}
+
+pub fn execute_proposal(
+    mut deps: DepsMut,
+    env: Env,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    if GOVERNANCE.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::GovernanceDisabled {});
+    }
+    let mut proposal = PROPOSALS.load(deps.storage, proposal_id)?;
+    if proposal.status != ProposalStatus::Open {
+        return Err(ContractError::ProposalNotOpen {});
+    }
+    if env.block.time < proposal.voting_ends {
+        return Err(ContractError::VotingNotEnded {});
+    }
+
+    let turnout = proposal.yes + proposal.no;
+    let passed = !proposal.yes.is_zero()
+        && turnout >= proposal.total_weight * proposal.quorum
+        && proposal.yes >= turnout * proposal.threshold;
+    let mut response = Response::new();
+    if passed {
+        // The state may have changed during the vote, a proposal which can no longer be
+        // applied is closed instead of staying open forever
+        proposal.status = match apply_proposal_action(deps.branch(), &env, &proposal.action) {
+            Ok(()) => ProposalStatus::Executed,
+            Err(err) => {
+                response = response.add_attribute("error", err.to_string());
+                ProposalStatus::Failed
+            }
+        };
+    } else {
+        proposal.status = ProposalStatus::Rejected;
+    }
+    PROPOSALS.save(deps.storage, proposal_id, &proposal)?;
+
+    Ok(response
+        .add_attribute("method", "execute_proposal")
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("passed", passed.to_string()))
+}
+
+// Every check is made before anything is written, a failed action leaves the state untouched
+fn apply_proposal_action(
+    deps: DepsMut,
+    env: &Env,
+    action: &ProposalAction,
+) -> Result<(), ContractError> {
+    match action {
+        ProposalAction::RegisterBeneficiary { beneficiary } => add_beneficiary(
+            deps.storage,
+            &env.block,
+            deps.api.addr_validate(beneficiary)?,
+        ),
+        ProposalAction::RemoveBeneficiary { beneficiary } => {
+            let beneficiary_addr = deps.api.addr_validate(beneficiary)?;
+            if FROZEN_BENEFICIARIES.has(deps.storage, beneficiary_addr.clone()) {
+                return Err(ContractError::BeneficiaryFrozen {});
+            }
+            move_beneficiary_to_removed(deps.storage, env.block.height, beneficiary_addr.clone())?;
+            EXPIRATIONS.remove(deps.storage, beneficiary_addr.clone());
+            release_bond(deps.storage, &beneficiary_addr, false)?;
+            Ok(())
+        }
+        ProposalAction::SetBeneficiaryCaps { beneficiary, caps } => {
+            let beneficiary_addr = deps.api.addr_validate(beneficiary)?;
+            validate_caps(deps.storage, &beneficiary_addr, caps)?;
+            if caps.is_empty() {
+                CAPS.remove(deps.storage, beneficiary_addr);
+            } else {
+                CAPS.save(deps.storage, beneficiary_addr, caps)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+pub fn admin_action(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    Ok(Response::new().add_attribute("method", "admin_action"))
+}
+
+// The owner holds every role, and admins hold every role but the ownership
+fn has_role(storage: &dyn Storage, addr: &Addr, role: Role) -> StdResult<bool> {
+    if STATE.load(storage)?.owner == *addr {
+        return Ok(true);
+    }
+    Ok(ROLES.has(storage, (Role::Admin.as_str(), addr.clone()))
+        || ROLES.has(storage, (role.as_str(), addr.clone())))
+}
+
+fn assert_role(storage: &dyn Storage, addr: &Addr, role: Role) -> Result<(), ContractError> {
+    if !has_role(storage, addr, role)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+pub fn grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    let addr = deps.api.addr_validate(&address)?;
+    if ROLES.has(deps.storage, (role.as_str(), addr.clone())) {
+        return Err(ContractError::RoleAlreadyGranted {});
+    }
+    ROLES.save(deps.storage, (role.as_str(), addr.clone()), &Empty {})?;
+    Ok(Response::new()
+        .add_attribute("method", "grant_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", addr))
+}
+
+pub fn set_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Pauser)?;
+    PAUSED.save(deps.storage, &paused)?;
+    Ok(Response::new()
+        .add_attribute("method", "set_paused")
+        .add_attribute("paused", paused.to_string()))
+}
+
+pub fn revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.storage, &info.sender, Role::Admin)?;
+    let addr = deps.api.addr_validate(&address)?;
+    if !ROLES.has(deps.storage, (role.as_str(), addr.clone())) {
+        return Err(ContractError::RoleNotGranted {});
+    }
+    ROLES.remove(deps.storage, (role.as_str(), addr.clone()));
+    Ok(Response::new()
+        .add_attribute("method", "revoke_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", addr))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetState {} => to_binary(&query_state(deps)?),
+        QueryMsg::GetDonator { donator } => to_binary(&query_donator(deps, donator)?),
+        QueryMsg::GetBeneficiary { beneficiary } => {
+            to_binary(&query_beneficiary(deps, env, beneficiary, &BENEFICIARIES)?)
+        }
+        QueryMsg::GetRemovedBeneficiary { beneficiary } => to_binary(&query_beneficiary(
+            deps,
+            env,
+            beneficiary,
+            &REMOVED_BENEFICIARIES,
+        )?),
+        QueryMsg::GetAllDonators {} => to_binary(&query_all_donators(deps)?),
+        QueryMsg::GetAllBeneficiaries {} => {
+            to_binary(&query_all_beneficiaries(deps, &BENEFICIARIES)?)
+        }
+        QueryMsg::GetAllRemovedBeneficiaries {} => {
+            to_binary(&query_all_beneficiaries(deps, &REMOVED_BENEFICIARIES)?)
+        }
+        QueryMsg::GetRound { round_id } => to_binary(&ROUNDS.load(deps.storage, round_id)?),
+        QueryMsg::GetRoundMatches { round_id } => to_binary(&query_round_matches(deps, round_id)?),
+        QueryMsg::GetRoundDonator { round_id, donator } => {
+            to_binary(&query_round_donator(deps, round_id, donator)?)
+        }
+        QueryMsg::GetRoundBeneficiary {
+            round_id,
+            beneficiary,
+        } => to_binary(&query_round_beneficiary(deps, env, round_id, beneficiary)?),
+        QueryMsg::GetGovernanceConfig {} => to_binary(&GovernanceConfigResponse {
+            config: GOVERNANCE.may_load(deps.storage)?,
+        }),
+        QueryMsg::GetProposal { proposal_id } => {
+            to_binary(&PROPOSALS.load(deps.storage, proposal_id)?)
+        }
+        QueryMsg::GetAllProposals {} => to_binary(&query_all_proposals(deps)?),
+        QueryMsg::GetRoleHolders {} => to_binary(&query_role_holders(deps)?),
+        QueryMsg::GetPaused {} => to_binary(&PausedResponse {
+            paused: PAUSED.may_load(deps.storage)?.unwrap_or_default(),
+        }),
+        QueryMsg::GetPendingOperations {} => to_binary(&query_pending_operations(deps)?),
+        QueryMsg::GetRegistrationConfig {} => to_binary(&query_registration_config(deps)?),
+        QueryMsg::GetBond { beneficiary } => to_binary(&query_bond(deps, beneficiary)?),
+        QueryMsg::GetInactiveBeneficiaries {} => {
+            to_binary(&query_inactive_beneficiaries(deps, env)?)
+        }
+        QueryMsg::SimulateDonation { funds } => to_binary(
+            &compute_distribution(deps.storage, &env.block, &funds)
+                .map_err(|err| StdError::generic_err(err.to_string()))?,
+        ),
+        QueryMsg::GetDonation { donation_id } => {
+            to_binary(&DONATIONS.load(deps.storage, donation_id)?)
+        }
+        QueryMsg::GetDonations { start_after, limit } => {
+            to_binary(&query_donations(deps, start_after, limit)?)
+        }
+        QueryMsg::GetDonatorDonations {
+            donator,
+            start_after,
+            limit,
+        } => {
+            let donator = if donator.starts_with(REMOTE_DONATOR_PREFIX) {
+                Addr::unchecked(donator)
+            } else {
+                deps.api.addr_validate(&donator)?
+            };
+            to_binary(&query_indexed_donations(
+                deps,
+                &DONATOR_DONATIONS,
+                donator,
+                start_after,
+                limit,
+            )?)
+        }
+        QueryMsg::GetBeneficiaryDonations {
+            beneficiary,
+            start_after,
+            limit,
+        } => to_binary(&query_indexed_donations(
+            deps,
+            &BENEFICIARY_DONATIONS,
+            deps.api.addr_validate(&beneficiary)?,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetAnonymousDonations {} => to_binary(&AnonymousDonationsResponse {
+            donations: ANONYMOUS_DONATIONS
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+        }),
+        QueryMsg::GetFreeze { freeze_id } => to_binary(&FREEZES.load(deps.storage, freeze_id)?),
+        QueryMsg::GetFreezes { start_after, limit } => {
+            to_binary(&query_freezes(deps, start_after, limit)?)
+        }
+        QueryMsg::GetTopDonators { denom, limit } => to_binary(&query_leaderboard(
+            deps,
+            &donator_leaderboard(),
+            denom,
+            limit,
+        )?),
+        QueryMsg::GetTopBeneficiaries { denom, limit } => to_binary(&query_leaderboard(
+            deps,
+            &beneficiary_leaderboard(),
+            denom,
+            limit,
+        )?),
+        QueryMsg::GetStats {} => to_binary(&query_stats(deps, &env)?),
+        QueryMsg::GetUnaccountedBalance {} => to_binary(&unaccounted_balance(deps, &env)?),
+        QueryMsg::GetGracePeriod {} => to_binary(&GracePeriodResponse {
+            seconds: GRACE_PERIOD.may_load(deps.storage)?,
+        }),
+        QueryMsg::GetMemoConfig {} => to_binary(&MemoConfigResponse {
+            config: MEMO_CONFIG.may_load(deps.storage)?,
+        }),
+        QueryMsg::GetEpochConfig {} => to_binary(&EpochConfigResponse {
+            config: EPOCH_CONFIG.may_load(deps.storage)?,
+            pending_pot: PENDING_POT.may_load(deps.storage)?.unwrap_or_default(),
+            next_distribution: NEXT_DISTRIBUTION.may_load(deps.storage)?,
+        }),
+        QueryMsg::GetEndowment {} => to_binary(&EndowmentResponse {
+            config: ENDOWMENT_CONFIG.may_load(deps.storage)?,
+            delegations: deps.querier.query_all_delegations(env.contract.address)?,
+            ledger: ENDOWMENT_LEDGER.may_load(deps.storage)?.unwrap_or_default(),
+        }),
+        QueryMsg::GetClaimable { beneficiary } => {
+            let beneficiary_addr = deps.api.addr_validate(&beneficiary)?;
+            to_binary(&ClaimableResponse {
+                claimable: CLAIMABLE
+                    .may_load(deps.storage, beneficiary_addr.clone())?
+                    .unwrap_or_default(),
+                beneficiary: beneficiary_addr,
+            })
+        }
+        QueryMsg::GetReceiptConfig {} => to_binary(&ReceiptConfigResponse {
+            config: RECEIPT_CONFIG.may_load(deps.storage)?,
+        }),
+        QueryMsg::GetSwapConfig {} => to_binary(&SwapConfigResponse {
+            config: SWAP_CONFIG.may_load(deps.storage)?,
+        }),
+        QueryMsg::GetAuction { auction_id } => to_binary(&AUCTIONS.load(deps.storage, auction_id)?),
+        QueryMsg::GetAuctions { start_after, limit } => {
+            to_binary(&query_auctions(deps, start_after, limit)?)
+        }
+        QueryMsg::GetHeldNfts {} => to_binary(&HeldNftListResponse {
+            nfts: HELD_NFTS
+                .range(deps.storage, None, None, Order::Ascending)
+                .map(|item| item.map(|(_, nft)| nft))
+                .collect::<StdResult<Vec<HeldNft>>>()?,
+        }),
+        QueryMsg::GetNftCollections {} => to_binary(&NftCollectionsResponse {
+            collections: NFT_COLLECTIONS.may_load(deps.storage)?.unwrap_or_default(),
+        }),
+        QueryMsg::GetEpoch { epoch_id } => to_binary(&EPOCHS.load(deps.storage, epoch_id)?),
+        QueryMsg::GetEpochs { start_after, limit } => {
+            to_binary(&query_epochs(deps, start_after, limit)?)
+        }
+    }
+}
+
+fn query_state(deps: Deps) -> StdResult<State> {
+    if let Ok(state) = STATE.load(deps.storage) {
+        return Ok(State {
+            owner: state.owner,
+            only_owner_can_register_beneficiary: state.only_owner_can_register_beneficiary,
+            distribution_strategy: state.distribution_strategy,
+        });
+    }
+    Err(StdError::GenericErr {
+        msg: "unable to load contract state".to_string(),
+    })
+}
+
+fn query_donator(deps: Deps, donator: String) -> StdResult<PotDonatorResponse> {
+    let donator_addr = if donator.starts_with(REMOTE_DONATOR_PREFIX) {
+        Addr::unchecked(donator)
+    } else {
+        deps.api.addr_validate(&donator)?
+    };
+    if let Ok(donator_infos) = DONATORS.load(deps.storage, donator_addr.clone()) {
+        return Ok(PotDonatorResponse {
+            donator: donator_addr,
+            donations: donator_infos,
+        });
+    }
+
+    Err(StdError::GenericErr {
+        msg: "Not a donator".to_string(),
+    })
+}
+
+fn beneficiary_response(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    beneficiary_addr: Addr,
+    received_donations: Vec<Coin>,
+) -> StdResult<BeneficiaryResponse> {
+    Ok(BeneficiaryResponse {
+        profile: PROFILES.may_load(storage, beneficiary_addr.clone())?,
+        expires: EXPIRATIONS.may_load(storage, beneficiary_addr.clone())?,
+        active: BENEFICIARIES.has(storage, beneficiary_addr.clone())
+            && is_active(storage, block, &beneficiary_addr)?
+            && !FROZEN_BENEFICIARIES.has(storage, beneficiary_addr.clone()),
+        freeze_id: FROZEN_BENEFICIARIES.may_load(storage, beneficiary_addr.clone())?,
+        caps: CAPS
+            .may_load(storage, beneficiary_addr.clone())?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|cap| {
+                let received = received_in_denom(&received_donations, &cap.denom);
+                CapStatus {
+                    reached: received >= cap.amount,
+                    cap,
+                    received,
+                }
+            })
+            .collect(),
+        denom_preference: DENOM_PREFERENCES.may_load(storage, beneficiary_addr.clone())?,
+        remote: REMOTE_ACCOUNTS.may_load(storage, beneficiary_addr.clone())?,
+        beneficiary: beneficiary_addr,
+        received_donations,
+    })
+}
+
+fn query_beneficiary(
+    deps: Deps,
+    env: Env,
+    beneficiary: String,
+    target: &Map<Addr, Vec<Coin>>,
+) -> StdResult<BeneficiaryResponse> {
+    let beneficiary_addr = deps.api.addr_validate(&beneficiary)?;
+    let beneficiary_addr = resolve_beneficiary(deps.storage, target, beneficiary_addr)?;
+    if let Ok(beneficiary_infos) = target.load(deps.storage, beneficiary_addr.clone()) {
+        return beneficiary_response(
+            deps.storage,
+            &env.block,
+            beneficiary_addr,
+            beneficiary_infos,
+        );
+    }
+
+    Err(StdError::GenericErr {
+        msg: "Not a beneficiary".to_string(),
+    })
+}
+
+fn query_all_donators(deps: Deps) -> StdResult<DonatorListResponse> {
+    let donators = DONATORS.keys(deps.storage, None, None, cosmwasm_std::Order::Ascending);
+    let donators: Result<Vec<Addr>, _> = donators.collect();
+    Ok(DonatorListResponse {
+        donators: donators?,
+    })
+}
+
+fn query_all_beneficiaries(
+    deps: Deps,
+    target: &Map<Addr, Vec<Coin>>,
+) -> StdResult<BeneficiaryListResponse> {
+    let beneficiaries = target.keys(deps.storage, None, None, cosmwasm_std::Order::Ascending);
+    let beneficiaries: Result<Vec<Addr>, _> = beneficiaries.collect();
+    Ok(BeneficiaryListResponse {
+        beneficiaries: beneficiaries?,
+    })
+}
+
+fn query_round_matches(deps: Deps, round_id: u64) -> StdResult<RoundMatchesResponse> {
+    let round = ROUNDS.load(deps.storage, round_id)?;
+    Ok(RoundMatchesResponse {
+        round_id,
+        matching_pool: round.matching_pool,
+        matches: compute_round_matches(deps.storage, &round)?,
+    })
+}
+
+fn query_round_donator(
+    deps: Deps,
+    round_id: u64,
+    donator: String,
+) -> StdResult<PotDonatorResponse> {
+    let donator_addr = deps.api.addr_validate(&donator)?;
+    if let Ok(donations) = ROUND_DONATORS.load(deps.storage, (round_id, donator_addr.clone())) {
+        return Ok(PotDonatorResponse {
+            donator: donator_addr,
+            donations,
+        });
+    }
+
+    Err(StdError::GenericErr {
+        msg: "Not a donator of this round".to_string(),
+    })
+}
+
+fn query_round_beneficiary(
+    deps: Deps,
+    env: Env,
+    round_id: u64,
+    beneficiary: String,
+) -> StdResult<BeneficiaryResponse> {
+    let beneficiary_addr = deps.api.addr_validate(&beneficiary)?;
+    if let Ok(received_donations) =
+        ROUND_BENEFICIARIES.load(deps.storage, (round_id, beneficiary_addr.clone()))
+    {
+        return beneficiary_response(
+            deps.storage,
+            &env.block,
+            beneficiary_addr,
+            received_donations,
+        );
+    }
+
+    Err(StdError::GenericErr {
+        msg: "Not a beneficiary of this round".to_string(),
+    })
+}
+
+fn query_all_proposals(deps: Deps) -> StdResult<ProposalListResponse> {
+    let proposals = PROPOSALS.range(deps.storage, None, None, Order::Ascending);
+    let proposals: Result<Vec<(u64, Proposal)>, _> = proposals.collect();
+    Ok(ProposalListResponse {
+        proposals: proposals?
+            .into_iter()
+            .map(|(_, proposal)| proposal)
+            .collect(),
+    })
+}
+
+fn query_role_holders(deps: Deps) -> StdResult<RoleHoldersResponse> {
+    let owner = STATE.load(deps.storage)?.owner;
+    let mut roles = Vec::new();
+    for role in Role::ALL {
+        let holders = ROLES
+            .prefix(role.as_str())
+            .keys(deps.storage, None, None, Order::Ascending);
+        let mut holders: Vec<Addr> = holders.collect::<Result<_, _>>()?;
+        if role == Role::Admin && !holders.contains(&owner) {
+            holders.insert(0, owner.clone());
+        }
+        roles.push(RoleHolders { role, holders });
+    }
+    Ok(RoleHoldersResponse { roles })
+}
+
+fn query_pending_operations(deps: Deps) -> StdResult<PendingOperationsResponse> {
+    let operations = PENDING_OPERATIONS.range(deps.storage, None, None, Order::Ascending);
+    let operations: Result<Vec<(u64, PendingOperation)>, _> = operations.collect();
+    Ok(PendingOperationsResponse {
+        delay: TIMELOCK_DELAY.may_load(deps.storage)?.unwrap_or_default(),
+        operations: operations?
+            .into_iter()
+            .map(|(_, operation)| operation)
+            .collect(),
+    })
+}
+
+fn query_registration_config(deps: Deps) -> StdResult<RegistrationConfigResponse> {
+    Ok(RegistrationConfigResponse {
+        config: REGISTRATION_CONFIG
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+        collected_fees: COLLECTED_FEES.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+fn query_bond(deps: Deps, beneficiary: String) -> StdResult<BondResponse> {
+    let beneficiary_addr = deps.api.addr_validate(&beneficiary)?;
+    Ok(BondResponse {
+        bond: BONDS.may_load(deps.storage, beneficiary_addr.clone())?,
+        beneficiary: beneficiary_addr,
+    })
+}
+
+fn query_inactive_beneficiaries(deps: Deps, env: Env) -> StdResult<BeneficiaryListResponse> {
+    let mut beneficiaries = Vec::new();
+    for beneficiary in BENEFICIARIES.keys(deps.storage, None, None, Order::Ascending) {
+        let beneficiary = beneficiary?;
+        if !is_active(deps.storage, &env.block, &beneficiary)? {
+            beneficiaries.push(beneficiary);
+        }
+    }
+    Ok(BeneficiaryListResponse { beneficiaries })
+}
+
+fn query_donations(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<DonationListResponse> {
+    let limit = limit
+        .unwrap_or(DEFAULT_DONATIONS_LIMIT)
+        .min(MAX_DONATIONS_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let donations: StdResult<Vec<Donation>> = DONATIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, donation)| donation))
+        .collect();
+    Ok(DonationListResponse {
+        donations: donations?,
+    })
+}
+
+fn query_indexed_donations(
+    deps: Deps,
+    index: &Map<(Addr, u64), Empty>,
+    address: Addr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<DonationListResponse> {
+    let limit = limit
+        .unwrap_or(DEFAULT_DONATIONS_LIMIT)
+        .min(MAX_DONATIONS_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let donations: StdResult<Vec<Donation>> = index
+        .prefix(address)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|donation_id| DONATIONS.load(deps.storage, donation_id?))
+        .collect();
+    Ok(DonationListResponse {
+        donations: donations?,
+    })
+}
+
+fn query_epochs(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<EpochListResponse> {
+    let limit = limit
+        .unwrap_or(DEFAULT_DONATIONS_LIMIT)
+        .min(MAX_DONATIONS_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let epochs: StdResult<Vec<Epoch>> = EPOCHS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, epoch)| epoch))
+        .collect();
+    Ok(EpochListResponse { epochs: epochs? })
+}
+
+fn query_stats(deps: Deps, env: &Env) -> StdResult<StatsResponse> {
+    let stats = STATS.may_load(deps.storage)?.unwrap_or_default();
+    Ok(StatsResponse {
+        donated: stats.donated,
+        paid_out: stats.paid_out,
+        fees_collected: stats.fees_collected,
+        dust: stats.dust,
+        donators: stats.donators,
+        registered_beneficiaries: stats.registered_beneficiaries,
+        // Expiring is not an event, the active ones are counted when queried
+        active_beneficiaries: active_beneficiaries(deps.storage, &env.block)?.len() as u64,
+        removed_beneficiaries: stats.removed_beneficiaries,
+    })
+}
+
+fn query_leaderboard(
+    deps: Deps,
+    leaderboard: &Leaderboard,
+    denom: String,
+    limit: Option<u32>,
+) -> StdResult<LeaderboardResponse> {
+    let limit = limit
+        .unwrap_or(DEFAULT_LEADERBOARD_LIMIT)
+        .min(MAX_LEADERBOARD_LIMIT) as usize;
+    let entries: StdResult<Vec<LeaderboardEntry>> = leaderboard
+        .idx
+        .amount
+        .sub_prefix(denom)
+        .range(deps.storage, None, None, Order::Descending)
+        .take(limit)
+        .map(|item| item.map(|(_, entry)| entry))
+        .collect();
+    Ok(LeaderboardResponse { entries: entries? })
+}
+
+fn query_freezes(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<FreezeListResponse> {
+    let limit = limit
+        .unwrap_or(DEFAULT_DONATIONS_LIMIT)
+        .min(MAX_DONATIONS_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let freezes: StdResult<Vec<Freeze>> = FREEZES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, freeze)| freeze))
+        .collect();
+    Ok(FreezeListResponse { freezes: freezes? })
+}
+
+fn query_auctions(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<AuctionListResponse> {
+    let limit = limit
+        .unwrap_or(DEFAULT_DONATIONS_LIMIT)
+        .min(MAX_DONATIONS_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let auctions: StdResult<Vec<Auction>> = AUCTIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, auction)| auction))
+        .collect();
+    Ok(AuctionListResponse {
+        auctions: auctions?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{
+        mock_dependencies_with_balance, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+        MOCK_CONTRACT_ADDR,
+    };
+    use cosmwasm_std::{
+        attr, coins, from_binary, from_slice, ContractResult, FullDelegation, OwnedDeps, Querier,
+        QuerierResult, QueryRequest, ReplyOn, SubMsgExecutionResponse, SystemResult, Validator,
+        WasmQuery,
+    };
+    use cw_utils::{Duration, Expiration};
+    use std::marker::PhantomData;
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &coins(1000, "token"));
+        // we can just call .unwrap() to assert this was a success
+        let res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+        assert_eq!(0, res.messages.len());
+        // it worked, let's query the state
+        let state = STATE.load(&deps.storage).expect("failed to load state");
+        assert_eq!(state.owner, owner_info.sender, "invalid owner");
+    }
+    #[test]
+    fn funds_distribution_2_beneficiary() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info, msg).unwrap();
+
+        // Create two beneficiaries
+        let beneficiary1_info = mock_info("beneficiary1", &coins(1, "token"));
+        let beneficiary2_info = mock_info("beneficiary2", &coins(1, "token"));
+
+        // Register beneficiaries
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            beneficiary1_info.clone(),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect("error occured while beneficiary2 tried to register");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            beneficiary2_info.clone(),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect("error occured while beneficiary2 tried to register");
+
+        // Create one donator
+        let donator1_info = mock_info("donator1", &coins(1000, "token"));
+
+        // Donate 1000 tokens
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            donator1_info,
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("error occured while donating");
+
+        // Query beneficiary donated funds
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: beneficiary1_info.sender.to_string(),
+            },
+        )
+        .expect("could not query beneficiary1 funds");
+        let beneficiary1_funds: BeneficiaryResponse = from_binary(&res).unwrap();
+        assert!(beneficiary1_funds.received_donations[0].amount == Uint128::from(500u32));
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: beneficiary2_info.sender.to_string(),
+            },
+        )
+        .expect("could not query beneficiary2 funds");
+        let beneficiary2_funds: BeneficiaryResponse = from_binary(&res).unwrap();
+        assert!(beneficiary2_funds.received_donations[0].amount == Uint128::from(500u32));
+    }
+    #[test]
+    fn test_only_admin_can_add_beneficiaries() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: true,
+        };
+        let owner_info = mock_info("owner", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+
+        // Create one beneficiary
+        let beneficiary_info = mock_info("beneficiary1", &coins(1, "token"));
+
+        // Register beneficiaries as user (should be failing)
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            beneficiary_info.clone(),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect_err("should be Unauthorized");
+
+        // Register beneficiaries as owner (should be working)
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            ExecuteMsg::RegisterBeneficiaryAsOwner {
+                beneficiary: beneficiary_info.sender.clone().to_string(),
+            },
+        )
+        .expect("owner failed to register beneficiary1 as a beneficiary");
+
+        // Create one donator
+        let donator1_info = mock_info("donator1", &coins(1000, "token"));
+
+        // Donate 1000 tokens
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            donator1_info,
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+
+        // Query beneficiary donated funds
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: beneficiary_info.sender.to_string(),
+            },
+        )
+        .expect("could not query beneficiary1 funds");
+        let beneficiary1_funds: BeneficiaryResponse = from_binary(&res).unwrap();
+        assert!(beneficiary1_funds.received_donations[0].amount == Uint128::from(1000u32));
+    }
+    #[test]
+    fn test_only_admin_or_beneficiary_can_remove_beneficiary() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+
+        // Create two beneficiaries
+        let beneficiary1_info = mock_info("beneficiary1", &coins(1, "token"));
+        let beneficiary2_info = mock_info("beneficiary2", &coins(1, "token"));
+
+        // Register beneficiaries as user
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            beneficiary1_info.clone(),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect("failed to add beneficiary1 as beneficiary");
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            beneficiary2_info.clone(),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect("failed to add beneficiary2 as beneficiary");
+
+        // Check that both beneficiaries are actually registered
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: beneficiary1_info.sender.to_string(),
+            },
+        )
+        .expect("beneficiary1 should be a beneficiary");
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: beneficiary2_info.sender.to_string(),
+            },
+        )
+        .expect("beneficiary2 should be a beneficiary");
+
+        // Non-owner should not be able to call "remove_beneficiary_as_owner"
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            beneficiary1_info.clone(),
+            ExecuteMsg::RemoveBeneficiaryAsOwner {
+                beneficiary: beneficiary2_info.sender.clone().to_string(),
+            },
+        )
+        .expect_err("should be Unauthorized");
+
+        // Non-owner should be able to remove itself
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            beneficiary1_info.clone(),
+            ExecuteMsg::RemoveBeneficiary {},
+        )
+        .expect("beneficiary1 should be able to remove itself from beneficiaries");
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: beneficiary1_info.sender.to_string(),
+            },
+        )
+        .expect_err("beneficiary1 should not be a beneficiary anymore");
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRemovedBeneficiary {
+                beneficiary: beneficiary1_info.sender.to_string(),
+            },
+        )
+        .expect("beneficiary1 should be in removed beneficiaries");
+
+        // Owner should be able to remove anyone
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            ExecuteMsg::RemoveBeneficiaryAsOwner {
+                beneficiary: beneficiary2_info.sender.clone().to_string(),
+            },
+        )
+        .expect("owner should be able to remove beneficiary2");
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: beneficiary2_info.sender.to_string(),
+            },
+        )
+        .expect_err("beneficiary2 should not be a beneficiary anymore");
+
+        // Both beneficiaries should be in the removed beneficiaries list
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetAllRemovedBeneficiaries {},
+        )
+        .expect("failed fetching beneficiairy list");
+        let removed_beneficiaries_list: BeneficiaryListResponse = from_binary(&res).unwrap();
+        assert_eq!(removed_beneficiaries_list.beneficiaries.len(), 2);
+        assert_eq!(
+            removed_beneficiaries_list.beneficiaries[0],
+            beneficiary1_info.sender
+        );
+        assert_eq!(
+            removed_beneficiaries_list.beneficiaries[1],
+            beneficiary2_info.sender
+        );
+    }
+    #[test]
+    fn test_split_between_100_beneficiaries() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+
+        // Create 100 beneficiaries
+        let mut beneficiaries_infos: Vec<MessageInfo> = Vec::new();
+        for i in 1..100 {
+            beneficiaries_infos.push(mock_info(&format!("beneficiary{}", i), &coins(1, "token")))
+        }
+        // Register each as a beneficiary
+        for beneficiary_info in &beneficiaries_infos {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                beneficiary_info.clone(),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("error occured while beneficiary tried to register");
+        }
+
+        // Donate funds
+        let donator_infos = mock_info("generous_donator", &coins(4500, "token"));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            donator_infos.clone(),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("donation failed");
+
+        // Each beneficiary should have 45 tokens
+        for beneficiary_info in &beneficiaries_infos {
+            let beneficiary_query_resp = query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetBeneficiary {
+                    beneficiary: beneficiary_info.sender.to_string(),
+                },
+            )
+            .expect("query funds of beneficiary failed");
+            let beneficiary_funds: BeneficiaryResponse =
+                from_binary(&beneficiary_query_resp).unwrap();
+            assert_eq!(
+                beneficiary_funds.received_donations[0].amount,
+                Uint128::from(45u32)
+            );
+        }
+    }
+    #[test]
+    fn test_tracking_of_deleted_and_restored_beneficiaries() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+
+        // Create one beneficiary
+        let beneficiary_info = mock_info("beneficiary1", &coins(1, "token"));
+
+        // Register beneficiary
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            beneficiary_info.clone(),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect("register beneficiary failed");
+
+        // Donate 1000 tokens
+        let donator1_info = mock_info("donator1", &coins(1000, "token"));
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            donator1_info,
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+
+        // Assert that funds have been correctly donated
+        let beneficiary_query_resp = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: beneficiary_info.sender.to_string(),
+            },
+        )
+        .expect("query funds of beneficiary failed");
+        let beneficiary_funds: BeneficiaryResponse = from_binary(&beneficiary_query_resp).unwrap();
+        let total_funds: Uint128 = beneficiary_funds
+            .received_donations
+            .iter()
+            .map(|funds| funds.amount)
+            .sum();
+        assert_eq!(total_funds, Uint128::from(1000u32));
+
+        // Remove beneficiary
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            beneficiary_info.clone(),
+            ExecuteMsg::RemoveBeneficiary {},
+        )
+        .expect("removing beneficiary failed");
+
+        // Funds should be in removed beneficiaries
+        let removed_beneficiary_query_resp = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRemovedBeneficiary {
+                beneficiary: beneficiary_info.sender.to_string(),
+            },
+        )
+        .expect("query funds of removed beneficiary failed");
+        let removed_beneficiary_funds: BeneficiaryResponse =
+            from_binary(&removed_beneficiary_query_resp).unwrap();
+        let total_funds: Uint128 = removed_beneficiary_funds
+            .received_donations
+            .iter()
+            .map(|funds| funds.amount)
+            .sum();
+        assert_eq!(total_funds, Uint128::from(1000u32));
+
+        // Register beneficiary again
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            beneficiary_info.clone(),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect("register beneficiary failed");
+
+        // Assert that removed_beneficiaries is empty
+        let _res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRemovedBeneficiary {
+                beneficiary: beneficiary_info.sender.to_string(),
+            },
+        )
+        .expect_err("beneficiary should not be in removed beneficiaries");
+
+        // Check that funds logs have been restored
+        let beneficiary_query_resp = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: beneficiary_info.sender.to_string(),
+            },
+        )
+        .expect("query funds of beneficiary failed");
+        let beneficiary_funds: BeneficiaryResponse = from_binary(&beneficiary_query_resp).unwrap();
+        let total_funds: Uint128 = beneficiary_funds
+            .received_donations
+            .iter()
+            .map(|funds| funds.amount)
+            .sum();
+        assert_eq!(total_funds, Uint128::from(1000u32));
+
+        // Donate 500 tokens
+        let donator1_info = mock_info("donator1", &coins(500, "token"));
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            donator1_info,
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+
+        // Check that funds have been received properly
+        let beneficiary_query_resp = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: beneficiary_info.sender.to_string(),
+            },
+        )
+        .expect("query funds of beneficiary failed");
+        let beneficiary_funds: BeneficiaryResponse = from_binary(&beneficiary_query_resp).unwrap();
+        let total_funds: Uint128 = beneficiary_funds
+            .received_donations
+            .iter()
+            .map(|funds| funds.amount)
+            .sum();
+        assert_eq!(total_funds, Uint128::from(1500u32));
+
+        // Remove beneficiary again (as owner)
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            ExecuteMsg::RemoveBeneficiaryAsOwner {
+                beneficiary: beneficiary_info.sender.to_string(),
+            },
+        )
+        .expect("removing beneficiary as owner failed");
+
+        // Query one last time from removed beneficiaries
+        let removed_beneficiary_query_resp = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRemovedBeneficiary {
+                beneficiary: beneficiary_info.sender.to_string(),
+            },
+        )
+        .expect("query funds of removed beneficiary failed");
+        let removed_beneficiary_funds: BeneficiaryResponse =
+            from_binary(&removed_beneficiary_query_resp).unwrap();
+        let total_funds: Uint128 = removed_beneficiary_funds
+            .received_donations
+            .iter()
+            .map(|funds| funds.amount)
+            .sum();
+        assert_eq!(total_funds, Uint128::from(1500u32));
+    }
+    #[test]
+    fn test_donations_are_not_received_by_removed_beneficiaries() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+
+        // Create two beneficiaries
+        let beneficiary1_info = mock_info("beneficiary1", &coins(1, "token"));
+        let beneficiary2_info = mock_info("beneficiary2", &coins(1, "token"));
+
+        // Register beneficiaries
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            beneficiary1_info.clone(),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect("register beneficiary1 failed");
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            beneficiary2_info.clone(),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect("register beneficiary failed");
+
+        // Remove beneficiary1
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            ExecuteMsg::RemoveBeneficiaryAsOwner {
+                beneficiary: beneficiary1_info.sender.to_string(),
+            },
+        )
+        .expect("removing beneficiary as owner failed");
+
+        // Donate funds
+        let donator1_info = mock_info("donator1", &coins(500, "token"));
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            donator1_info,
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+
+        // Assert that beneficiary1 received none of the funds
+        let _res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: beneficiary1_info.sender.to_string(),
+            },
+        )
+        .expect_err("should not be able to get beneficiary1 as a regular one");
+        let removed_beneficiary1_query_resp = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRemovedBeneficiary {
+                beneficiary: beneficiary1_info.sender.to_string(),
+            },
+        )
+        .expect("query funds of removed beneficiary failed");
+        let removed_beneficiary1_funds: BeneficiaryResponse =
+            from_binary(&removed_beneficiary1_query_resp).unwrap();
+        let total_funds: Uint128 = removed_beneficiary1_funds
+            .received_donations
+            .iter()
+            .map(|funds| funds.amount)
+            .sum();
+        assert_eq!(total_funds, Uint128::from(0u32));
+
+        // Check that beneficiary2 received all funds
+        let beneficiary2_query_resp = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: beneficiary2_info.sender.to_string(),
+            },
+        )
+        .expect("query funds of removed beneficiary failed");
+        let beneficiary2_funds: BeneficiaryResponse =
+            from_binary(&beneficiary2_query_resp).unwrap();
+        let total_funds: Uint128 = beneficiary2_funds
+            .received_donations
+            .iter()
+            .map(|funds| funds.amount)
+            .sum();
+        assert_eq!(total_funds, Uint128::from(500u32));
+    }
+    #[test]
+    fn test_quadratic_funding_round() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &coins(1000, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+
+        // Register two beneficiaries
+        for beneficiary in ["beneficiary1", "beneficiary2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &[]),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("register beneficiary failed");
+        }
+
+        // Owner opens a round with a matching pool of 1000 tokens
+        let start = mock_env().block.time;
+        let end = start.plus_seconds(100);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::CreateRound {
+                start,
+                end,
+                denom: "token".to_string(),
+            },
+        )
+        .expect("failed to create round");
+
+        // Four donators give 25 tokens to beneficiary1, one donator gives 100 tokens to beneficiary2
+        for i in 1..5 {
+            let res = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(&format!("donator{}", i), &coins(25, "token")),
+                ExecuteMsg::ContributeToRound {
+                    round_id: 1,
+                    beneficiary: "beneficiary1".to_string(),
+                },
+            )
+            .expect("failed to contribute to round");
+            assert_eq!(res.messages.len(), 1);
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("whale", &coins(100, "token")),
+            ExecuteMsg::ContributeToRound {
+                round_id: 1,
+                beneficiary: "beneficiary2".to_string(),
+            },
+        )
+        .expect("failed to contribute to round");
+
+        // Broad support is favored: (4 * 5)^2 = 400 against 10^2 = 100
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRoundMatches { round_id: 1 },
+        )
+        .expect("failed to query round matches");
+        let round_matches: RoundMatchesResponse = from_binary(&res).unwrap();
+        assert_eq!(round_matches.matches.len(), 2);
+        assert_eq!(
+            round_matches.matches[0].contributions,
+            Uint128::from(100u32)
+        );
+        assert_eq!(
+            round_matches.matches[0].projected_match,
+            Uint128::from(800u32)
+        );
+        assert_eq!(
+            round_matches.matches[1].projected_match,
+            Uint128::from(200u32)
+        );
+
+        // Round contributions are not mixed with the regular pot accounting
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetDonator {
+                donator: "whale".to_string(),
+            },
+        )
+        .expect_err("round contributions should not be registered as pot donations");
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRoundDonator {
+                round_id: 1,
+                donator: "whale".to_string(),
+            },
+        )
+        .expect("whale should be a donator of the round");
+
+        // Round can't be finalized before it ends
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::FinalizeRound { round_id: 1 },
+        )
+        .expect_err("round should not be finalized before its end");
+
+        let mut env = mock_env();
+        env.block.time = end;
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("donator1", &coins(25, "token")),
+            ExecuteMsg::ContributeToRound {
+                round_id: 1,
+                beneficiary: "beneficiary1".to_string(),
+            },
+        )
+        .expect_err("round should be closed");
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::FinalizeRound { round_id: 1 },
+        )
+        .expect("failed to finalize round");
+        assert_eq!(res.messages.len(), 2);
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRoundBeneficiary {
+                round_id: 1,
+                beneficiary: "beneficiary1".to_string(),
+            },
+        )
+        .expect("failed to query round beneficiary");
+        let beneficiary1_funds: BeneficiaryResponse = from_binary(&res).unwrap();
+        let total_funds: Uint128 = beneficiary1_funds
+            .received_donations
+            .iter()
+            .map(|funds| funds.amount)
+            .sum();
+        assert_eq!(total_funds, Uint128::from(900u32));
+
+        // Without any contribution the whole matching pool goes back to its funders
+        let start = end;
+        let end = start.plus_seconds(100);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &coins(500, "token")),
+            ExecuteMsg::CreateRound {
+                start,
+                end,
+                denom: "token".to_string(),
+            },
+        )
+        .expect("failed to create round");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sponsor", &coins(250, "token")),
+            ExecuteMsg::FundRound { round_id: 2 },
+        )
+        .expect("failed to fund round");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sponsor", &[]),
+            ExecuteMsg::ReclaimMatchingPool { round_id: 2 },
+        )
+        .expect_err("round is not finalized yet");
+        let mut env = mock_env();
+        env.block.time = end;
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::FinalizeRound { round_id: 2 },
+        )
+        .expect("failed to finalize round");
+        assert!(res.messages.is_empty());
+        for (funder, amount) in [("owner", 500), ("sponsor", 250)] {
+            let res = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(funder, &[]),
+                ExecuteMsg::ReclaimMatchingPool { round_id: 2 },
+            )
+            .expect("failed to reclaim the matching pool");
+            assert_eq!(
+                res.messages[0].msg,
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: funder.to_string(),
+                    amount: coins(amount, "token"),
+                })
+            );
+        }
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sponsor", &[]),
+            ExecuteMsg::ReclaimMatchingPool { round_id: 2 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToReclaim {}));
+    }
+    #[test]
+    fn test_beneficiaries_governance() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: true,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+
+        // Owner registers three beneficiaries
+        for beneficiary in ["beneficiary1", "beneficiary2", "beneficiary3"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                owner_info.clone(),
+                ExecuteMsg::RegisterBeneficiaryAsOwner {
+                    beneficiary: beneficiary.to_string(),
+                },
+            )
+            .expect("owner failed to register beneficiary");
+        }
+
+        // Governance is disabled by default
+        let action = ProposalAction::RegisterBeneficiary {
+            beneficiary: "newcomer".to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary1", &[]),
+            ExecuteMsg::Propose {
+                action: action.clone(),
+            },
+        )
+        .expect_err("governance should be disabled");
+
+        // Only the owner can enable governance
+        let config = GovernanceConfig {
+            voters: VoterSet::Beneficiaries,
+            quorum: Decimal::percent(50),
+            threshold: Decimal::percent(50),
+            voting_period: 100,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary1", &[]),
+            ExecuteMsg::SetGovernanceConfig {
+                config: Some(config.clone()),
+            },
+        )
+        .expect_err("should be Unauthorized");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::SetGovernanceConfig {
+                config: Some(config),
+            },
+        )
+        .expect("owner failed to enable governance");
+
+        // Voting weights are those at the start of the block the proposal is made in
+        let mut next_block = mock_env();
+        next_block.block.height += 1;
+        // Non-beneficiaries can't propose
+        execute(
+            deps.as_mut(),
+            next_block.clone(),
+            mock_info("newcomer", &[]),
+            ExecuteMsg::Propose {
+                action: action.clone(),
+            },
+        )
+        .expect_err("newcomer is not a voter");
+        execute(
+            deps.as_mut(),
+            next_block.clone(),
+            mock_info("beneficiary1", &[]),
+            ExecuteMsg::Propose { action },
+        )
+        .expect("beneficiary1 failed to propose");
+
+        // Two beneficiaries approve, one refuses
+        for (voter, approve) in [
+            ("beneficiary1", true),
+            ("beneficiary2", true),
+            ("beneficiary3", false),
+        ] {
+            execute(
+                deps.as_mut(),
+                next_block.clone(),
+                mock_info(voter, &[]),
+                ExecuteMsg::Vote {
+                    proposal_id: 1,
+                    approve,
+                },
+            )
+            .expect("failed to vote");
+        }
+        execute(
+            deps.as_mut(),
+            next_block.clone(),
+            mock_info("beneficiary1", &[]),
+            ExecuteMsg::Vote {
+                proposal_id: 1,
+                approve: true,
+            },
+        )
+        .expect_err("beneficiary1 already voted");
+
+        // Proposal can only be executed once the voting period is over
+        execute(
+            deps.as_mut(),
+            next_block.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ExecuteProposal { proposal_id: 1 },
+        )
+        .expect_err("voting period is not over");
+        let mut env = next_block;
+        env.block.time = env.block.time.plus_seconds(100);
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::ExecuteProposal { proposal_id: 1 },
+        )
+        .expect("failed to execute proposal");
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetProposal { proposal_id: 1 },
+        )
+        .expect("failed to query proposal");
+        let proposal: Proposal = from_binary(&res).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Executed);
+        assert_eq!(proposal.yes, Uint128::from(2u32));
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: "newcomer".to_string(),
+            },
+        )
+        .expect("newcomer should be a beneficiary");
+
+        // Caps can be changed by governance as well, a proposal which can no longer be applied
+        // once passed is closed as failed
+        let mut env = mock_env();
+        env.block.height += 2;
+        env.block.time = env.block.time.plus_seconds(200);
+        for action in [
+            ProposalAction::RegisterBeneficiary {
+                beneficiary: "latecomer".to_string(),
+            },
+            ProposalAction::SetBeneficiaryCaps {
+                beneficiary: "beneficiary2".to_string(),
+                caps: coins(500, "token"),
+            },
+        ] {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("beneficiary1", &[]),
+                ExecuteMsg::Propose { action },
+            )
+            .expect("beneficiary1 failed to propose");
+        }
+        for proposal_id in [2, 3] {
+            for voter in ["beneficiary1", "beneficiary2", "beneficiary3"] {
+                execute(
+                    deps.as_mut(),
+                    env.clone(),
+                    mock_info(voter, &[]),
+                    ExecuteMsg::Vote {
+                        proposal_id,
+                        approve: true,
+                    },
+                )
+                .expect("failed to vote");
+            }
+        }
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            ExecuteMsg::RegisterBeneficiaryAsOwner {
+                beneficiary: "latecomer".to_string(),
+            },
+        )
+        .expect("owner failed to register beneficiary");
+        env.block.time = env.block.time.plus_seconds(100);
+        for proposal_id in [2, 3] {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("anyone", &[]),
+                ExecuteMsg::ExecuteProposal { proposal_id },
+            )
+            .expect("failed to execute proposal");
+        }
+        let statuses: Vec<ProposalStatus> = [2, 3]
+            .into_iter()
+            .map(|proposal_id| {
+                let res = query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::GetProposal { proposal_id },
+                )
+                .unwrap();
+                from_binary::<Proposal>(&res).unwrap().status
+            })
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![ProposalStatus::Failed, ProposalStatus::Executed]
+        );
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: "beneficiary2".to_string(),
+            },
+        )
+        .unwrap();
+        let beneficiary: BeneficiaryResponse = from_binary(&res).unwrap();
+        assert_eq!(beneficiary.caps[0].cap, Coin::new(500, "token"));
+    }
+    #[test]
+    fn test_donators_governance_rejects_without_quorum() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary1", &[]),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect("register beneficiary failed");
+
+        // A small and a large donator
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("small_donator", &coins(100, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("large_donator", &coins(900, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::SetGovernanceConfig {
+                config: Some(GovernanceConfig {
+                    voters: VoterSet::Donators {
+                        denom: "token".to_string(),
+                    },
+                    quorum: Decimal::percent(20),
+                    threshold: Decimal::percent(50),
+                    voting_period: 100,
+                }),
+            },
+        )
+        .expect("owner failed to enable governance");
+
+        // The small donator alone doesn't reach the 20% quorum
+        let mut next_block = mock_env();
+        next_block.block.height += 1;
+        execute(
+            deps.as_mut(),
+            next_block.clone(),
+            mock_info("small_donator", &[]),
+            ExecuteMsg::Propose {
+                action: ProposalAction::RemoveBeneficiary {
+                    beneficiary: "beneficiary1".to_string(),
+                },
+            },
+        )
+        .expect("small_donator failed to propose");
+        execute(
+            deps.as_mut(),
+            next_block.clone(),
+            mock_info("small_donator", &[]),
+            ExecuteMsg::Vote {
+                proposal_id: 1,
+                approve: true,
+            },
+        )
+        .expect("failed to vote");
+
+        // Donating once the proposal is made doesn't give any weight for it
+        execute(
+            deps.as_mut(),
+            next_block.clone(),
+            mock_info("latecomer", &coins(5000, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+        let mut later_block = next_block.clone();
+        later_block.block.height += 1;
+        let err = execute(
+            deps.as_mut(),
+            later_block,
+            mock_info("latecomer", &[]),
+            ExecuteMsg::Vote {
+                proposal_id: 1,
+                approve: true,
+            },
+        )
+        .expect_err("latecomer was not a donator when the proposal was made");
+        assert!(matches!(err, ContractError::NotAVoter {}));
+
+        let mut env = next_block;
+        env.block.time = env.block.time.plus_seconds(100);
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::ExecuteProposal { proposal_id: 1 },
+        )
+        .expect("failed to execute proposal");
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetProposal { proposal_id: 1 },
+        )
+        .expect("failed to query proposal");
+        let proposal: Proposal = from_binary(&res).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Rejected);
+        assert_eq!(proposal.total_weight, Uint128::from(1000u32));
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: "beneficiary1".to_string(),
+            },
+        )
+        .expect("beneficiary1 should still be a beneficiary");
+    }
+    #[test]
+    fn test_role_based_access_control() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: true,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+        let manager_info = mock_info("manager", &[]);
+
+        // Manager can't register beneficiaries before being granted the role
+        let register_msg = ExecuteMsg::RegisterBeneficiaryAsOwner {
+            beneficiary: "beneficiary1".to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            manager_info.clone(),
+            register_msg.clone(),
+        )
+        .expect_err("should be Unauthorized");
+
+        // Only admins can grant roles
+        let grant_msg = ExecuteMsg::GrantRole {
+            role: Role::BeneficiaryManager,
+            address: "manager".to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            manager_info.clone(),
+            grant_msg.clone(),
+        )
+        .expect_err("should be Unauthorized");
+        execute(deps.as_mut(), mock_env(), owner_info.clone(), grant_msg)
+            .expect("owner failed to grant role");
+
+        // Manager can now register and remove beneficiaries, but not grant roles
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            manager_info.clone(),
+            register_msg.clone(),
+        )
+        .expect("manager failed to register beneficiary1");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            manager_info.clone(),
+            ExecuteMsg::RemoveBeneficiaryAsOwner {
+                beneficiary: "beneficiary1".to_string(),
+            },
+        )
+        .expect("manager failed to remove beneficiary1");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            manager_info.clone(),
+            ExecuteMsg::GrantRole {
+                role: Role::Admin,
+                address: "manager".to_string(),
+            },
+        )
+        .expect_err("should be Unauthorized");
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetRoleHolders {})
+            .expect("failed to query role holders");
+        let role_holders: RoleHoldersResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            role_holders.roles,
+            vec![
+                RoleHolders {
+                    role: Role::Admin,
+                    holders: vec![owner_info.sender.clone()],
+                },
+                RoleHolders {
+                    role: Role::BeneficiaryManager,
+                    holders: vec![manager_info.sender.clone()],
+                },
+                RoleHolders {
+                    role: Role::FeeManager,
+                    holders: vec![],
+                },
+                RoleHolders {
+                    role: Role::Pauser,
+                    holders: vec![],
+                },
+            ]
+        );
+
+        // Once revoked, manager loses its privileges
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::RevokeRole {
+                role: Role::BeneficiaryManager,
+                address: "manager".to_string(),
+            },
+        )
+        .expect("owner failed to revoke role");
+        execute(deps.as_mut(), mock_env(), manager_info, register_msg)
+            .expect_err("should be Unauthorized");
+    }
+    #[test]
+    fn test_timelocked_owner_operations() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary1", &[]),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect("register beneficiary failed");
+
+        // No timelock yet, setting the delay is immediate
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            ExecuteMsg::SetTimelockDelay { delay: 3600 },
+        )
+        .expect("owner failed to set the timelock delay");
+
+        // Removing a beneficiary as owner is now queued
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            ExecuteMsg::RemoveBeneficiaryAsOwner {
+                beneficiary: "beneficiary1".to_string(),
+            },
+        )
+        .expect("owner failed to queue beneficiary removal");
+        assert_eq!(res.attributes[0].value, "queue_operation");
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: "beneficiary1".to_string(),
+            },
+        )
+        .expect("beneficiary1 should still be a beneficiary");
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetPendingOperations {})
+            .expect("failed to query pending operations");
+        let pending: PendingOperationsResponse = from_binary(&res).unwrap();
+        assert_eq!(pending.delay, 3600);
+        assert_eq!(pending.operations.len(), 1);
+        assert_eq!(pending.operations[0].proposer, owner_info.sender);
+
+        // Operation can't be executed during the delay nor cancelled by someone else
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ExecuteOperation { operation_id: 1 },
+        )
+        .expect_err("operation should not be executable yet");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::CancelOperation { operation_id: 1 },
+        )
+        .expect_err("should be Unauthorized");
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(3600);
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::ExecuteOperation { operation_id: 1 },
+        )
+        .expect("failed to execute operation");
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRemovedBeneficiary {
+                beneficiary: "beneficiary1".to_string(),
+            },
+        )
+        .expect("beneficiary1 should be in removed beneficiaries");
+
+        // Queued operations can be cancelled during the delay
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            ExecuteMsg::SetTimelockDelay { delay: 0 },
+        )
+        .expect("owner failed to queue the timelock delay change");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::CancelOperation { operation_id: 2 },
+        )
+        .expect("owner failed to cancel operation");
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetPendingOperations {})
+            .expect("failed to query pending operations");
+        let pending: PendingOperationsResponse = from_binary(&res).unwrap();
+        assert_eq!(pending.delay, 3600);
+        assert!(pending.operations.is_empty());
+
+        // Fee withdrawals are queued as well
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::WithdrawFees {
+                to: "treasury".to_string(),
+            },
+        )
+        .expect("owner failed to queue the fees withdrawal");
+        assert_eq!(res.attributes[0].value, "queue_operation");
+        assert!(res.messages.is_empty());
+    }
+    #[test]
+    fn test_beneficiary_profiles() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+        let beneficiary_info = mock_info("beneficiary1", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            beneficiary_info.clone(),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect("register beneficiary failed");
+
+        // Oversized fields are rejected
+        let profile = BeneficiaryProfile {
+            display_name: Some("Docs sprint".to_string()),
+            description: Some("x".repeat(MAX_DESCRIPTION_LENGTH + 1)),
+            url: Some("https://example.com".to_string()),
+            logo_uri: None,
+            tags: vec!["docs".to_string()],
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            beneficiary_info.clone(),
+            ExecuteMsg::SetBeneficiaryProfile {
+                beneficiary: None,
+                profile: Some(profile.clone()),
+            },
+        )
+        .expect_err("description should be too long");
+
+        // Beneficiary edits its own profile
+        let profile = BeneficiaryProfile {
+            description: Some("Improving the documentation".to_string()),
+            ..profile
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            beneficiary_info.clone(),
+            ExecuteMsg::SetBeneficiaryProfile {
+                beneficiary: None,
+                profile: Some(profile.clone()),
+            },
+        )
+        .expect("beneficiary failed to set its profile");
+
+        // Someone else can't edit it
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::SetBeneficiaryProfile {
+                beneficiary: Some("beneficiary1".to_string()),
+                profile: None,
+            },
+        )
+        .expect_err("should be Unauthorized");
+
+        // Profile is kept once the beneficiary is removed
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::RemoveBeneficiaryAsOwner {
+                beneficiary: "beneficiary1".to_string(),
+            },
+        )
+        .expect("owner failed to remove beneficiary1");
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRemovedBeneficiary {
+                beneficiary: "beneficiary1".to_string(),
+            },
+        )
+        .expect("query removed beneficiary failed");
+        let beneficiary: BeneficiaryResponse = from_binary(&res).unwrap();
+        assert_eq!(beneficiary.profile, Some(profile));
+    }
+    #[test]
+    fn test_beneficiary_address_rotation() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info, msg).unwrap();
+        let old_info = mock_info("old_wallet", &[]);
+        let new_info = mock_info("new_wallet", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            old_info.clone(),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect("register beneficiary failed");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(1000, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            old_info.clone(),
+            ExecuteMsg::ChangeBeneficiaryAddress {
+                new_address: "new_wallet".to_string(),
+            },
+        )
+        .expect("failed to request address change");
+
+        // Only the new address can confirm the change
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("attacker", &[]),
+            ExecuteMsg::ConfirmBeneficiaryAddress {
+                old_address: "old_wallet".to_string(),
+            },
+        )
+        .expect_err("attacker should not be able to confirm");
+        CLAIMABLE
+            .save(
+                deps.as_mut().storage,
+                old_info.sender.clone(),
+                &coins(10, "token"),
+            )
+            .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            new_info.clone(),
+            ExecuteMsg::ConfirmBeneficiaryAddress {
+                old_address: "old_wallet".to_string(),
+            },
+        )
+        .expect("failed to confirm address change");
+
+        // The claimable balance moves along
+        let claimable: ClaimableResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetClaimable {
+                    beneficiary: "new_wallet".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(claimable.claimable, coins(10, "token"));
+        assert!(!CLAIMABLE.has(&deps.storage, old_info.sender.clone()));
+
+        // Going back to the old address would make the forwards loop
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            new_info.clone(),
+            ExecuteMsg::ChangeBeneficiaryAddress {
+                new_address: "old_wallet".to_string(),
+            },
+        )
+        .expect_err("old_wallet is forwarded already");
+
+        // History is kept, and queries on the old address are forwarded
+        for address in ["old_wallet", "new_wallet"] {
+            let res = query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetBeneficiary {
+                    beneficiary: address.to_string(),
+                },
+            )
+            .expect("query beneficiary failed");
+            let beneficiary: BeneficiaryResponse = from_binary(&res).unwrap();
+            assert_eq!(beneficiary.beneficiary, new_info.sender);
+            assert_eq!(
+                beneficiary.received_donations[0].amount,
+                Uint128::from(1000u32)
+            );
+        }
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetAllBeneficiaries {})
+            .expect("failed fetching beneficiary list");
+        let beneficiaries: BeneficiaryListResponse = from_binary(&res).unwrap();
+        assert_eq!(beneficiaries.beneficiaries, vec![new_info.sender.clone()]);
+
+        // Old address isn't a beneficiary anymore
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            old_info,
+            ExecuteMsg::RemoveBeneficiary {},
+        )
+        .expect_err("old_wallet should not be a beneficiary anymore");
+
+        // Even with a loop in the forwards, queries on a removed address terminate
+        BENEFICIARY_FORWARDS
+            .save(
+                deps.as_mut().storage,
+                new_info.sender.clone(),
+                &Addr::unchecked("old_wallet"),
+            )
+            .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            new_info,
+            ExecuteMsg::RemoveBeneficiary {},
+        )
+        .expect("failed to remove beneficiary");
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: "old_wallet".to_string(),
+            },
+        )
+        .expect_err("old_wallet is not a beneficiary anymore");
+    }
+    #[test]
+    fn test_registration_bond_and_fee() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+
+        // Registering costs a bond of 100 tokens and a fee of 10 tokens
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            ExecuteMsg::SetRegistrationConfig {
+                config: RegistrationConfig {
+                    bond: Some(Coin::new(100, "token")),
+                    fee: Some(Coin::new(10, "token")),
+                    duration: None,
+                },
+            },
+        )
+        .expect("owner failed to set the registration config");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary1", &coins(100, "token")),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect_err("fee is missing");
+        for beneficiary in ["beneficiary1", "beneficiary2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &coins(110, "token")),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("register beneficiary failed");
+        }
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBond {
+                beneficiary: "beneficiary1".to_string(),
+            },
+        )
+        .expect("failed to query bond");
+        let bond: BondResponse = from_binary(&res).unwrap();
+        assert_eq!(bond.bond, Some(Coin::new(100, "token")));
+
+        // Leaving voluntarily refunds the bond
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary1", &[]),
+            ExecuteMsg::RemoveBeneficiary {},
+        )
+        .expect("beneficiary1 failed to leave");
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "beneficiary1".to_string(),
+                amount: coins(100, "token"),
+            })
+        );
+
+        // Being removed by the owner slashes the bond
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            ExecuteMsg::RemoveBeneficiaryAsOwner {
+                beneficiary: "beneficiary2".to_string(),
+            },
+        )
+        .expect("owner failed to remove beneficiary2");
+        assert!(res.messages.is_empty());
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRegistrationConfig {},
+        )
+        .expect("failed to query registration config");
+        let registration: RegistrationConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(registration.collected_fees, coins(120, "token"));
+
+        // Fees are withdrawn by fee managers only
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary2", &[]),
+            ExecuteMsg::WithdrawFees {
+                to: "beneficiary2".to_string(),
+            },
+        )
+        .expect_err("should be Unauthorized");
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::WithdrawFees {
+                to: "treasury".to_string(),
+            },
+        )
+        .expect("owner failed to withdraw fees");
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "treasury".to_string(),
+                amount: coins(120, "token"),
+            })
+        );
+    }
+    #[test]
+    fn test_expiring_registrations() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+
+        // Registrations last 100 blocks
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::SetRegistrationConfig {
+                config: RegistrationConfig {
+                    duration: Some(Duration::Height(100)),
+                    ..RegistrationConfig::default()
+                },
+            },
+        )
+        .expect("owner failed to set the registration config");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary1", &[]),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect("register beneficiary1 failed");
+        let mut env = mock_env();
+        env.block.height += 50;
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("beneficiary2", &[]),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect("register beneficiary2 failed");
+
+        // Once expired, beneficiary1 is skipped by donations
+        env.block.height += 50;
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("donator1", &coins(500, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+        assert_eq!(res.messages.len(), 1);
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::GetInactiveBeneficiaries {},
+        )
+        .expect("failed to query inactive beneficiaries");
+        let inactive: BeneficiaryListResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            inactive.beneficiaries,
+            vec![Addr::unchecked("beneficiary1")]
+        );
+
+        // Renewing makes it active again
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("beneficiary1", &[]),
+            ExecuteMsg::RenewRegistration {},
+        )
+        .expect("failed to renew registration");
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: "beneficiary1".to_string(),
+            },
+        )
+        .expect("query beneficiary failed");
+        let beneficiary: BeneficiaryResponse = from_binary(&res).unwrap();
+        assert!(beneficiary.active);
+        assert_eq!(
+            beneficiary.expires,
+            Some(Expiration::AtHeight(env.block.height + 100))
+        );
+
+        // Permissionless cleanup moves expired beneficiaries out, a page at a time
+        env.block.height += 50;
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::CleanupExpiredBeneficiaries {
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .expect("cleanup failed");
+        assert_eq!(res.messages.len(), 0);
+        assert!(res
+            .attributes
+            .contains(&attr("last_visited", "beneficiary1")));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::CleanupExpiredBeneficiaries {
+                start_after: Some("beneficiary1".to_string()),
+                limit: None,
+            },
+        )
+        .expect("cleanup failed");
+        assert!(res.attributes.contains(&attr("last_visited", "")));
+        query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::GetRemovedBeneficiary {
+                beneficiary: "beneficiary2".to_string(),
+            },
+        )
+        .expect("beneficiary2 should be in removed beneficiaries");
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetAllBeneficiaries {})
+            .expect("failed fetching beneficiary list");
+        let beneficiaries: BeneficiaryListResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            beneficiaries.beneficiaries,
+            vec![Addr::unchecked("beneficiary1")]
+        );
+
+        // Until it renews its registration
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("beneficiary2", &[]),
+            ExecuteMsg::RenewRegistration {},
+        )
+        .expect("failed to renew registration");
+        query(
+            deps.as_ref(),
+            env,
+            QueryMsg::GetBeneficiary {
+                beneficiary: "beneficiary2".to_string(),
+            },
+        )
+        .expect("beneficiary2 should be a beneficiary again");
+    }
+    #[test]
+    fn test_beneficiary_caps() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+        for beneficiary in ["beneficiary1", "beneficiary2", "beneficiary3"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &[]),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("failed to register beneficiary");
+        }
+
+        // Only beneficiary managers can set caps
+        let set_caps = |beneficiary: &str, caps: Vec<Coin>| ExecuteMsg::SetBeneficiaryCaps {
+            beneficiary: beneficiary.to_string(),
+            caps,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary1", &[]),
+            set_caps("beneficiary1", coins(1000, "token")),
+        )
+        .expect_err("beneficiaries can't set their own cap");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            set_caps(
+                "beneficiary1",
+                vec![Coin::new(100, "token"), Coin::new(200, "token")],
+            ),
+        )
+        .expect_err("a denom can only be capped once");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            set_caps("beneficiary1", coins(100, "token")),
+        )
+        .expect("owner failed to set a cap");
+
+        // beneficiary1 share above its cap is redistributed, other denoms are not capped
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(
+                "donator1",
+                &[Coin::new(600, "token"), Coin::new(30, "other")],
+            ),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "beneficiary1".to_string(),
+                amount: vec![Coin::new(100, "token"), Coin::new(10, "other")],
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "beneficiary2".to_string(),
+                amount: vec![Coin::new(250, "token"), Coin::new(10, "other")],
+            })
+        );
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: "beneficiary1".to_string(),
+            },
+        )
+        .expect("query beneficiary failed");
+        let beneficiary: BeneficiaryResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            beneficiary.caps,
+            vec![CapStatus {
+                cap: Coin::new(100, "token"),
+                received: Uint128::new(100),
+                reached: true,
+            }]
+        );
+
+        // Once every cap is reached, the rest goes back to the donator
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            set_caps("beneficiary2", coins(300, "token")),
+        )
+        .expect("owner failed to set a cap");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            set_caps("beneficiary3", coins(250, "token")),
+        )
+        .expect("owner failed to set a cap");
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator2", &coins(100, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "beneficiary2".to_string(),
+                amount: coins(50, "token"),
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "donator2".to_string(),
+                amount: coins(50, "token"),
+            })
+        );
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetDonator {
+                donator: "donator2".to_string(),
+            },
+        )
+        .expect("query donator failed");
+        let donator: PotDonatorResponse = from_binary(&res).unwrap();
+        assert_eq!(donator.donations, coins(50, "token"));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator2", &coins(100, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect_err("every beneficiary reached its cap");
+    }
+    #[test]
+    fn test_denom_preferences() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let _res = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        for beneficiary in ["beneficiary1", "beneficiary2", "beneficiary3"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &[]),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("failed to register beneficiary");
+        }
+
+        // beneficiary1 only takes "token", beneficiary2 takes anything but "other"
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary1", &[]),
+            ExecuteMsg::SetDenomPreference {
+                beneficiary: None,
+                preference: Some(DenomPreference::Accept {
+                    denoms: vec!["token".to_string()],
+                }),
+            },
+        )
+        .expect("failed to set denom preference");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary2", &[]),
+            ExecuteMsg::SetDenomPreference {
+                beneficiary: None,
+                preference: Some(DenomPreference::Exclude {
+                    denoms: vec!["other".to_string()],
+                }),
+            },
+        )
+        .expect("failed to set denom preference");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary2", &[]),
+            ExecuteMsg::SetDenomPreference {
+                beneficiary: Some("beneficiary3".to_string()),
+                preference: None,
+            },
+        )
+        .expect_err("only beneficiary managers can set someone else preference");
+
+        // Each denom is only split amongst the beneficiaries accepting it
+        let funds = vec![
+            Coin::new(300, "token"),
+            Coin::new(100, "stake"),
+            Coin::new(50, "other"),
+        ];
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateDonation {
+                funds: funds.clone(),
+            },
+        )
+        .expect("failed to simulate donation");
+        let simulation: SimulateDonationResponse = from_binary(&res).unwrap();
+        let expected_payouts = vec![
+            Payout {
+                beneficiary: Addr::unchecked("beneficiary1"),
+                amount: coins(100, "token"),
+            },
+            Payout {
+                beneficiary: Addr::unchecked("beneficiary2"),
+                amount: vec![Coin::new(100, "token"), Coin::new(50, "stake")],
+            },
+            Payout {
+                beneficiary: Addr::unchecked("beneficiary3"),
+                amount: vec![
+                    Coin::new(100, "token"),
+                    Coin::new(50, "stake"),
+                    Coin::new(50, "other"),
+                ],
+            },
+        ];
+        assert_eq!(simulation.payouts, expected_payouts);
+        assert!(simulation.refunded.is_empty());
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &funds),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+        assert_eq!(res.messages.len(), 3);
+
+        // The donation history keeps the recipients of each denom
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetDonations {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .expect("failed to query donations");
+        let history: DonationListResponse = from_binary(&res).unwrap();
+        assert_eq!(history.donations.len(), 1);
+        assert_eq!(history.donations[0].donator, Addr::unchecked("donator1"));
+        assert_eq!(history.donations[0].payouts, expected_payouts);
+
+        // A denom nobody accepts is refunded
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::SetDenomPreference {
+                beneficiary: Some("beneficiary3".to_string()),
+                preference: Some(DenomPreference::Exclude {
+                    denoms: vec!["other".to_string()],
+                }),
+            },
+        )
+        .expect("owner failed to set denom preference");
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateDonation {
+                funds: vec![Coin::new(30, "token"), Coin::new(10, "other")],
+            },
+        )
+        .expect("failed to simulate donation");
+        let simulation: SimulateDonationResponse = from_binary(&res).unwrap();
+        assert_eq!(simulation.payouts.len(), 3);
+        assert_eq!(simulation.refunded, coins(10, "other"));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(10, "other")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect_err("nobody accepts this denom");
+
+        // Managers can only change someone else's preference after the timelock
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::SetTimelockDelay { delay: 3600 },
+        )
+        .expect("owner failed to set the timelock delay");
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::SetDenomPreference {
+                beneficiary: Some("beneficiary3".to_string()),
+                preference: Some(DenomPreference::Accept {
+                    denoms: vec!["nonexistent".to_string()],
+                }),
+            },
+        )
+        .expect("owner failed to queue the denom preference change");
+        assert_eq!(res.attributes[0].value, "queue_operation");
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: "beneficiary3".to_string(),
+            },
+        )
+        .unwrap();
+        let beneficiary: BeneficiaryResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            beneficiary.denom_preference,
+            Some(DenomPreference::Exclude {
+                denoms: vec!["other".to_string()],
+            })
+        );
+    }
+    #[test]
+    fn test_distribution_strategies() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+        for beneficiary in ["beneficiary1", "beneficiary2", "beneficiary3"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &[]),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("failed to register beneficiary");
+        }
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary1", &[]),
+            ExecuteMsg::SetDistributionStrategy {
+                strategy: DistributionStrategy::RoundRobin,
+            },
+        )
+        .expect_err("only admins can change the strategy");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            ExecuteMsg::SetDistributionStrategy {
+                strategy: DistributionStrategy::RoundRobin,
+            },
+        )
+        .expect("owner failed to change the strategy");
+
+        // Each donation goes wholly to the next beneficiary
+        for expected in [
+            "beneficiary1",
+            "beneficiary2",
+            "beneficiary3",
+            "beneficiary1",
+        ] {
+            let res = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("donator1", &coins(10, "token")),
+                ExecuteMsg::AddToPot {
+                    ibc_origin: None,
+                    memo: None,
+                    anonymous: None,
+                    min_output: None,
+                },
+            )
+            .expect("failed to donate tokens");
+            assert_eq!(
+                res.messages,
+                vec![SubMsg::new(BankMsg::Send {
+                    to_address: expected.to_string(),
+                    amount: coins(10, "token"),
+                })]
+            );
+        }
+
+        // The lottery picks a single beneficiary, the same the simulation announced
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::SetDistributionStrategy {
+                strategy: DistributionStrategy::Lottery,
+            },
+        )
+        .expect("owner failed to change the strategy");
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateDonation {
+                funds: coins(10, "token"),
+            },
+        )
+        .expect("failed to simulate donation");
+        let simulation: SimulateDonationResponse = from_binary(&res).unwrap();
+        assert_eq!(simulation.payouts.len(), 1);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(10, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+
+        // The strategy in effect is kept with each donation
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetDonations {
+                start_after: Some(3),
+                limit: None,
+            },
+        )
+        .expect("failed to query donations");
+        let history: DonationListResponse = from_binary(&res).unwrap();
+        assert_eq!(history.donations.len(), 2);
+        assert_eq!(
+            history.donations[0].strategy,
+            DistributionStrategy::RoundRobin
+        );
+        assert_eq!(history.donations[1].strategy, DistributionStrategy::Lottery);
+        assert_eq!(history.donations[1].payouts, simulation.payouts);
+    }
+    #[test]
+    fn test_distribution_epochs() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+        for beneficiary in ["beneficiary1", "beneficiary2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &[]),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("failed to register beneficiary");
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::SetEpochConfig {
+                config: Some(EpochConfig {
+                    duration: Duration::Height(10),
+                }),
+            },
+        )
+        .expect("owner failed to set the epoch config");
+
+        // Donations are only accumulated
+        for donator in ["donator1", "donator2"] {
+            let res = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(donator, &coins(51, "token")),
+                ExecuteMsg::AddToPot {
+                    ibc_origin: None,
+                    memo: None,
+                    anonymous: None,
+                    min_output: None,
+                },
+            )
+            .expect("failed to donate tokens");
+            assert!(res.messages.is_empty());
+        }
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetEpochConfig {})
+            .expect("failed to query epoch config");
+        let epoch_config: EpochConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(epoch_config.pending_pot, coins(102, "token"));
+
+        // Anyone can distribute the pending pot, once per epoch
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::Distribute {},
+        )
+        .expect("failed to distribute");
+        assert_eq!(res.messages.len(), 2);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(11, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::Distribute {},
+        )
+        .expect_err("epoch has not ended");
+        let mut env = mock_env();
+        env.block.height += 10;
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::Distribute {},
+        )
+        .expect("failed to distribute");
+
+        let res = query(
+            deps.as_ref(),
+            env,
+            QueryMsg::GetEpochs {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .expect("failed to query epochs");
+        let epochs: EpochListResponse = from_binary(&res).unwrap();
+        assert_eq!(epochs.epochs.len(), 2);
+        assert_eq!(
+            epochs.epochs[0].payouts[0],
+            Payout {
+                beneficiary: Addr::unchecked("beneficiary1"),
+                amount: coins(51, "token"),
+            }
+        );
+        assert!(epochs.epochs[0].carried_over.is_empty());
+        // Rounding dust waits for the next epoch
+        assert_eq!(epochs.epochs[1].payouts[0].amount, coins(5, "token"));
+        assert_eq!(epochs.epochs[1].carried_over, coins(1, "token"));
+    }
+    // Answers swap simulations at a fixed rate, anything else goes to the mock querier
+    struct RouterQuerier {
+        base: MockQuerier,
+        rate: Decimal,
+    }
+
+    impl Querier for RouterQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let request: QueryRequest<Empty> = from_slice(bin_request).unwrap();
+            match request {
+                QueryRequest::Wasm(WasmQuery::Smart { msg, .. }) => {
+                    let SwapRouterQueryMsg::SimulateSwap { offer, .. } = from_binary(&msg).unwrap();
+                    let simulation = SimulateSwapResponse {
+                        amount: offer.amount * self.rate,
+                    };
+                    SystemResult::Ok(ContractResult::Ok(to_binary(&simulation).unwrap()))
+                }
+                _ => self.base.raw_query(bin_request),
+            }
+        }
+    }
+
+    #[test]
+    fn test_swap_donations() {
+        // Instantiating smart contract, the donated "usd" is already part of its balance
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: RouterQuerier {
+                base: MockQuerier::new(&[(MOCK_CONTRACT_ADDR, &coins(20, "usd"))]),
+                rate: Decimal::percent(200),
+            },
+            custom_query_type: PhantomData,
+        };
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+        for beneficiary in ["beneficiary1", "beneficiary2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &[]),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("failed to register beneficiary");
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::SetSwapConfig {
+                config: Some(SwapConfig {
+                    target_denom: "usd".to_string(),
+                    router: Addr::unchecked("router"),
+                    max_slippage: Decimal::percent(1),
+                }),
+            },
+        )
+        .expect("owner failed to set the swap config");
+
+        // Other denoms are only swapped with a minimum output set by the donator
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &[Coin::new(100, "atom"), Coin::new(20, "usd")]),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MinOutputRequired {}));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &[Coin::new(100, "atom"), Coin::new(20, "usd")]),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: Some(Uint128::new(195)),
+            },
+        )
+        .expect("failed to donate tokens");
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_success(
+                WasmMsg::Execute {
+                    contract_addr: "router".to_string(),
+                    msg: to_binary(&SwapRouterExecuteMsg::Swap {
+                        ask_denom: "usd".to_string(),
+                        min_output: Uint128::new(198),
+                    })
+                    .unwrap(),
+                    funds: coins(100, "atom"),
+                },
+                SWAP_REPLY_ID,
+            )]
+        );
+
+        // The output is split once the swap is done
+        deps.querier
+            .base
+            .update_balance(MOCK_CONTRACT_ADDR, coins(219, "usd"));
+        let res = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: SWAP_REPLY_ID,
+                result: SubMsgResult::Ok(SubMsgExecutionResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        )
+        .expect("failed to handle the swap reply");
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "beneficiary2".to_string(),
+                amount: coins(109, "usd"),
+            })
+        );
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetDonation { donation_id: 1 },
+        )
+        .expect("failed to query donation");
+        let donation: Donation = from_binary(&res).unwrap();
+        assert_eq!(donation.funds, coins(219, "usd"));
+        assert_eq!(
+            donation.swap,
+            Some(SwapRecord {
+                offered: coins(100, "atom"),
+                received: Coin::new(199, "usd"),
+            })
+        );
+
+        // Swaps returning less than the donator minimum fail
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(100, "atom")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: Some(Uint128::new(200)),
+            },
+        )
+        .expect("failed to donate tokens");
+        deps.querier
+            .base
+            .update_balance(MOCK_CONTRACT_ADDR, coins(369, "usd"));
+        let err = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: SWAP_REPLY_ID,
+                result: SubMsgResult::Ok(SubMsgExecutionResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SwapOutputTooLow {}));
+    }
+    #[test]
+    fn test_endowment_mode() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "stake"));
+        let validator = |address: &str| Validator {
+            address: address.to_string(),
+            commission: Decimal::percent(5),
+            max_commission: Decimal::percent(10),
+            max_change_rate: Decimal::percent(1),
+        };
+        deps.querier.update_staking(
+            "stake",
+            &[validator("validator1"), validator("validator2")],
+            &[FullDelegation {
+                delegator: Addr::unchecked(MOCK_CONTRACT_ADDR),
+                validator: "validator1".to_string(),
+                amount: Coin::new(51, "stake"),
+                can_redelegate: Coin::new(51, "stake"),
+                accumulated_rewards: coins(10, "stake"),
+            }],
+        );
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+        for beneficiary in ["beneficiary1", "beneficiary2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &[]),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("failed to register beneficiary");
+        }
+        let endowment = |validators: &[&str]| ExecuteMsg::SetEndowmentConfig {
+            config: Some(EndowmentConfig {
+                denom: "stake".to_string(),
+                validators: validators.iter().map(|v| v.to_string()).collect(),
+            }),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            endowment(&["validator3"]),
+        )
+        .expect_err("unknown validator");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            endowment(&["validator1", "validator2"]),
+        )
+        .expect("owner failed to set the endowment config");
+
+        // Donations are delegated instead of paid out
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(101, "stake")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(StakingMsg::Delegate {
+                    validator: "validator1".to_string(),
+                    amount: Coin::new(51, "stake"),
+                }),
+                SubMsg::new(StakingMsg::Delegate {
+                    validator: "validator2".to_string(),
+                    amount: Coin::new(50, "stake"),
+                }),
+            ]
+        );
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(101, "other")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect_err("only the staking denom can be endowed");
+
+        // Delegating to validator1 withdrew its rewards, they are harvested with the next ones
+        let staked = |rewards: u128| {
+            [FullDelegation {
+                delegator: Addr::unchecked(MOCK_CONTRACT_ADDR),
+                validator: "validator1".to_string(),
+                amount: Coin::new(102, "stake"),
+                can_redelegate: Coin::new(102, "stake"),
+                accumulated_rewards: coins(rewards, "stake"),
+            }]
+        };
+        deps.querier.update_staking(
+            "stake",
+            &[validator("validator1"), validator("validator2")],
+            &staked(4),
+        );
+
+        // Anyone can harvest the rewards, which are fanned out
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::HarvestRewards {},
+        )
+        .expect("failed to harvest rewards");
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
+                    validator: "validator1".to_string(),
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "beneficiary1".to_string(),
+                    amount: coins(7, "stake"),
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "beneficiary2".to_string(),
+                    amount: coins(7, "stake"),
+                }),
+            ]
+        );
+        deps.querier.update_staking(
+            "stake",
+            &[validator("validator1"), validator("validator2")],
+            &staked(0),
+        );
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::HarvestRewards {},
+        )
+        .expect_err("rewards were already harvested");
+
+        // Only admins can move the delegations, between configured validators
+        let undelegate = ExecuteMsg::Undelegate {
+            validator: "validator1".to_string(),
+            amount: Coin::new(51, "stake"),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            undelegate.clone(),
+        )
+        .expect_err("only admins can undelegate");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            ExecuteMsg::Redelegate {
+                src_validator: "validator1".to_string(),
+                dst_validator: "validator3".to_string(),
+                amount: Coin::new(51, "stake"),
+            },
+        )
+        .expect_err("validator3 is not configured");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            ExecuteMsg::Undelegate {
+                validator: "validator3".to_string(),
+                amount: Coin::new(51, "stake"),
+            },
+        )
+        .expect_err("nothing is delegated to validator3");
+        let res = execute(deps.as_mut(), mock_env(), owner_info.clone(), undelegate)
+            .expect("owner failed to undelegate");
+        assert_eq!(res.messages.len(), 1);
+
+        // The undelegated principal is kept apart from the rewards, until delegated again
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetEndowment {})
+            .expect("failed to query the endowment");
+        let endowment: EndowmentResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            endowment.ledger,
+            EndowmentLedger {
+                withdrawn_rewards: vec![],
+                undelegated: coins(51, "stake"),
+            }
+        );
+        // and is not mistaken for royalties
+        let res: UnaccountedBalanceResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetUnaccountedBalance {},
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(res.held.contains(&Coin::new(51, "stake")));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            ExecuteMsg::Delegate {
+                validator: "validator2".to_string(),
+                amount: Coin::new(52, "stake"),
+            },
+        )
+        .expect_err("only 51 stake were undelegated");
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::Delegate {
+                validator: "validator2".to_string(),
+                amount: Coin::new(51, "stake"),
+            },
+        )
+        .expect("owner failed to delegate the principal again");
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(StakingMsg::Delegate {
+                validator: "validator2".to_string(),
+                amount: Coin::new(51, "stake"),
+            })]
+        );
+    }
+    #[test]
+    fn test_remote_beneficiaries() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let _res = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        for beneficiary in ["beneficiary1", "beneficiary2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &[]),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("failed to register beneficiary");
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary2", &[]),
+            ExecuteMsg::SetRemoteAccount {
+                beneficiary: None,
+                remote: Some(RemoteAccount {
+                    channel_id: "channel-0".to_string(),
+                    address: "osmo1beneficiary2".to_string(),
+                }),
+            },
+        )
+        .expect("failed to set remote account");
+
+        // Remote beneficiaries are paid over IBC
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(100, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+        assert_eq!(res.messages[1].id, IBC_TRANSFER_REPLY_ID);
+        assert_eq!(res.messages[1].reply_on, ReplyOn::Always);
+        match &res.messages[1].msg {
+            CosmosMsg::Stargate { type_url, value } => {
+                assert_eq!(type_url, "/ibc.applications.transfer.v1.MsgTransfer");
+                let value = String::from_utf8_lossy(value.as_slice());
+                for field in ["channel-0", "osmo1beneficiary2", "token", "50"] {
+                    assert!(value.contains(field));
+                }
+                // ibc-hooks only reports back to the contract named in the memo
+                assert!(value.contains(&format!(r#"{{"ibc_callback":"{}"}}"#, MOCK_CONTRACT_ADDR)));
+            }
+            msg => panic!("unexpected message {:?}", msg),
+        }
+
+        // The packet sequence comes with the reply, a timeout makes the payout claimable
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: IBC_TRANSFER_REPLY_ID,
+                result: SubMsgResult::Ok(SubMsgExecutionResponse {
+                    events: vec![],
+                    data: Some(Binary::from(vec![0x08, 0x96, 0x01])),
+                }),
+            },
+        )
+        .expect("failed to handle the transfer reply");
+        sudo(
+            deps.as_mut(),
+            mock_env(),
+            SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcTimeout {
+                channel: "channel-0".to_string(),
+                sequence: 150,
+            }),
+        )
+        .expect("failed to handle the timeout");
+
+        // Transfers which could not even be sent are claimable as well
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(20, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: IBC_TRANSFER_REPLY_ID,
+                result: SubMsgResult::Err("channel closed".to_string()),
+            },
+        )
+        .expect("failed to handle the transfer reply");
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetClaimable {
+                beneficiary: "beneficiary2".to_string(),
+            },
+        )
+        .expect("failed to query claimable payouts");
+        let claimable: ClaimableResponse = from_binary(&res).unwrap();
+        assert_eq!(claimable.claimable, coins(60, "token"));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary2", &[]),
+            ExecuteMsg::ClaimPayouts {},
+        )
+        .expect("failed to claim payouts");
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "beneficiary2".to_string(),
+                amount: coins(60, "token"),
+            })
+        );
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary2", &[]),
+            ExecuteMsg::ClaimPayouts {},
+        )
+        .expect_err("nothing left to claim");
+
+        // Without any sequence in the reply, the transfer is matched with the next unknown
+        // callback of its channel
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(40, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: IBC_TRANSFER_REPLY_ID,
+                result: SubMsgResult::Ok(SubMsgExecutionResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        )
+        .expect("failed to handle the transfer reply");
+        sudo(
+            deps.as_mut(),
+            mock_env(),
+            SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcAck {
+                channel: "channel-0".to_string(),
+                sequence: 151,
+                ack: "error".to_string(),
+                success: false,
+            }),
+        )
+        .expect("failed to handle the acknowledgement");
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetClaimable {
+                beneficiary: "beneficiary2".to_string(),
+            },
+        )
+        .expect("failed to query claimable payouts");
+        let claimable: ClaimableResponse = from_binary(&res).unwrap();
+        assert_eq!(claimable.claimable, coins(20, "token"));
+
+        // Managers can only redirect someone else's payouts after the timelock
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::SetTimelockDelay { delay: 3600 },
+        )
+        .expect("owner failed to set the timelock delay");
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::SetRemoteAccount {
+                beneficiary: Some("beneficiary1".to_string()),
+                remote: Some(RemoteAccount {
+                    channel_id: "channel-1".to_string(),
+                    address: "osmo1owner".to_string(),
+                }),
+            },
+        )
+        .expect("owner failed to queue the remote account change");
+        assert_eq!(res.attributes[0].value, "queue_operation");
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBeneficiary {
+                beneficiary: "beneficiary1".to_string(),
+            },
+        )
+        .unwrap();
+        let beneficiary: BeneficiaryResponse = from_binary(&res).unwrap();
+        assert_eq!(beneficiary.remote, None);
+    }
+    #[test]
+    fn test_ibc_hooks_donations() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let _res = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary1", &[]),
+            ExecuteMsg::RegisterBeneficiary {},
+        )
+        .expect("failed to register beneficiary");
+
+        // ibc-hooks calls the contract from an address derived from the channel and sender
+        let origin = IbcOrigin {
+            channel: "channel-0".to_string(),
+            sender: "osmo1remotedonator".to_string(),
+        };
+        let hooks_sender = ibc_hooks_sender("channel-0", "osmo1remotedonator", "wasm").unwrap();
+        assert!(hooks_sender.starts_with("wasm1"));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(&hooks_sender, &coins(100, "ibc/token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: Some(IbcOrigin {
+                    channel: "channel-1".to_string(),
+                    sender: "osmo1remotedonator".to_string(),
+                }),
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect_err("origin does not match the sender");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(100, "ibc/token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: Some(origin.clone()),
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect_err("only ibc-hooks can donate on behalf of a remote sender");
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(&hooks_sender, &coins(100, "ibc/token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::IbcOriginRequired {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(&hooks_sender, &coins(100, "ibc/token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: Some(origin.clone()),
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+
+        // The donation is recorded under the remote identity
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetDonator {
+                donator: "ibc/channel-0/osmo1remotedonator".to_string(),
+            },
+        )
+        .expect("remote donator should be recorded");
+        let donator: PotDonatorResponse = from_binary(&res).unwrap();
+        assert_eq!(donator.donations, coins(100, "ibc/token"));
+
+        // Undistributable donations fail, so that the transfer is refunded on the other chain
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary1", &[]),
+            ExecuteMsg::SetDenomPreference {
+                beneficiary: None,
+                preference: Some(DenomPreference::Exclude {
+                    denoms: vec!["ibc/other".to_string()],
+                }),
+            },
+        )
+        .expect("failed to set denom preference");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(
+                &hooks_sender,
+                &[Coin::new(100, "ibc/token"), Coin::new(10, "ibc/other")],
+            ),
+            ExecuteMsg::AddToPot {
+                ibc_origin: Some(origin),
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect_err("remote donations can't be partially refunded");
+    }
+
+    #[test]
+    fn test_donation_receipts() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+        for beneficiary in ["beneficiary1", "beneficiary2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &[]),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("failed to register beneficiary");
+        }
+        let receipt_config = ExecuteMsg::SetReceiptConfig {
+            config: Some(ReceiptConfig {
+                collection: Addr::unchecked("receipts"),
+                token_uri: None,
+            }),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &[]),
+            receipt_config.clone(),
+        )
+        .expect_err("only admins can configure receipts");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            receipt_config,
+        )
+        .expect("owner failed to configure receipts");
+
+        // Each donation mints a receipt to its donator
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(100, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+        assert_eq!(res.messages.len(), 3);
+        assert_eq!(
+            res.messages[2].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "receipts".to_string(),
+                msg: to_binary(&Cw721ExecuteMsg::Mint {
+                    token_id: "1".to_string(),
+                    owner: "donator1".to_string(),
+                    token_uri: None,
+                    extension: ReceiptMetadata {
+                        donation_id: 1,
+                        amount: coins(100, "token"),
+                        denoms: vec!["token".to_string()],
+                        timestamp: mock_env().block.time,
+                        pot: mock_env().contract.address,
+                        beneficiaries: 2,
+                    },
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+
+        // A donation without funds is rejected instead of minting a free receipt
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator2", &[]),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFunds {}));
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetDonation { donation_id: 2 },
+        )
+        .expect_err("no donation should be recorded");
+
+        // Until receipts are turned off
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::SetReceiptConfig { config: None },
+        )
+        .expect("owner failed to turn receipts off");
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(100, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+        assert_eq!(res.messages.len(), 2);
+    }
+
+    #[test]
+    fn test_nft_auctions_and_royalties() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+        for beneficiary in ["beneficiary1", "beneficiary2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &[]),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("failed to register beneficiary");
+        }
+
+        // Only NFTs from allowed collections are accepted
+        let receive_nft = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: "artist".to_string(),
+            token_id: "1".to_string(),
+            msg: Binary::default(),
+        });
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("collection", &[]),
+            receive_nft.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CollectionNotAllowed {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            ExecuteMsg::SetNftCollections {
+                collections: vec!["collection".to_string()],
+            },
+        )
+        .expect("owner failed to allow the collection");
+
+        // An NFT sent without payload is held
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("collection", &[]),
+            ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: "artist".to_string(),
+                token_id: "1".to_string(),
+                msg: Binary::default(),
+            }),
+        )
+        .expect("failed to receive nft");
+        let held: HeldNftListResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetHeldNfts {}).unwrap())
+                .unwrap();
+        assert_eq!(held.nfts.len(), 1);
+
+        let start_auction = ExecuteMsg::StartAuction {
+            collection: "collection".to_string(),
+            token_id: "1".to_string(),
+            reserve_price: Coin::new(100, "token"),
+            duration: 100,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &[]),
+            start_auction.clone(),
+        )
+        .expect_err("only admins can auction held nfts");
+        execute(deps.as_mut(), mock_env(), owner_info, start_auction)
+            .expect("owner failed to start auction");
+
+        let bid = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+                   bidder: &str,
+                   amount: u128| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(bidder, &coins(amount, "token")),
+                ExecuteMsg::Bid { auction_id: 1 },
+            )
+        };
+        let err = bid(&mut deps, "bidder1", 50).unwrap_err();
+        assert!(matches!(err, ContractError::BidTooLow {}));
+        bid(&mut deps, "bidder1", 100).expect("failed to bid");
+        let err = bid(&mut deps, "bidder2", 100).unwrap_err();
+        assert!(matches!(err, ContractError::BidTooLow {}));
+        // Outbidding refunds the previous bidder
+        let res = bid(&mut deps, "bidder2", 150).expect("failed to outbid");
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "bidder1".to_string(),
+                amount: coins(100, "token"),
+            })
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::SettleAuction { auction_id: 1 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::AuctionNotEnded {}));
+
+        // The proceeds are split and the NFT goes to the winner
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::SettleAuction { auction_id: 1 },
+        )
+        .expect("failed to settle auction");
+        assert_eq!(res.messages.len(), 3);
+        assert_eq!(
+            res.messages[2].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "collection".to_string(),
+                msg: to_binary(&Cw721ExecuteMsg::TransferNft {
+                    recipient: "bidder2".to_string(),
+                    token_id: "1".to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        let donator: PotDonatorResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetDonator {
+                    donator: "artist".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(donator.donations, coins(150, "token"));
+        let err = bid(&mut deps, "bidder1", 200).unwrap_err();
+        assert!(matches!(err, ContractError::AuctionNotActive {}));
+
+        // A second NFT is auctioned on reception and gets a bid escrowed by the contract
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("collection", &[]),
+            ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: "artist".to_string(),
+                token_id: "2".to_string(),
+                msg: to_binary(&ReceiveNftMsg::Auction {
+                    reserve_price: Coin::new(100, "token"),
+                    duration: 100,
+                })
+                .unwrap(),
+            }),
+        )
+        .expect("failed to receive nft for auction");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bidder1", &coins(100, "token")),
+            ExecuteMsg::Bid { auction_id: 2 },
+        )
+        .expect("failed to bid");
+
+        // Royalties are whatever the contract holds on top of the escrowed bid
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(300, "token"));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::DistributeRoyalties {},
+        )
+        .expect("failed to distribute royalties");
+        assert_eq!(res.messages.len(), 2);
+        // The contract does not become a donator
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetDonator {
+                donator: MOCK_CONTRACT_ADDR.to_string(),
+            },
+        )
+        .expect_err("royalties are not recorded as donations of the contract");
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "beneficiary1".to_string(),
+                amount: coins(100, "token"),
+            })
+        );
 
-fn query_all_beneficiaries(
-    deps: Deps,
-    target: &Map<Addr, Vec<Coin>>,
-) -> StdResult<BeneficiaryListResponse> {
-    let beneficiaries = target.keys(deps.storage, None, None, cosmwasm_std::Order::Ascending);
-    let beneficiaries: Result<Vec<Addr>, _> = beneficiaries.collect();
-    return Ok(BeneficiaryListResponse {
-        beneficiaries: beneficiaries?,
-    });
-}
+        // The rounding dust is counted once and left to Sweep
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(201, "token"));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::DistributeRoyalties {},
+        )
+        .expect("failed to distribute royalties");
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(101, "token"));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::DistributeRoyalties {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToDistribute {}));
+        let stats: StatsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetStats {}).unwrap()).unwrap();
+        assert_eq!(stats.dust, coins(1, "token"));
+        let res: UnaccountedBalanceResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetUnaccountedBalance {},
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.sweepable, coins(1, "token"));
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies_with_balance, mock_env, mock_info};
-    use cosmwasm_std::{coins, from_binary};
     #[test]
-    fn proper_initialization() {
+    fn test_donation_memos() {
+        // Instantiating smart contract
         let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
         let msg = InstantiateMsg {
             only_owner_can_register_beneficiary: false,
         };
-        let owner_info = mock_info("owner", &coins(1000, "token"));
-        // we can just call .unwrap() to assert this was a success
-        let res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
-        assert_eq!(0, res.messages.len());
-        // it worked, let's query the state
-        let state = STATE.load(&deps.storage).expect("failed to load state");
-        assert_eq!(state.owner, owner_info.sender, "invalid owner");
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+        for beneficiary in ["beneficiary1", "beneficiary2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &[]),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("failed to register beneficiary");
+        }
+        let donate_with_memo = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+                                memo: &str| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("donator1", &coins(100, "token")),
+                ExecuteMsg::AddToPot {
+                    ibc_origin: None,
+                    memo: Some(memo.to_string()),
+                    anonymous: None,
+                    min_output: None,
+                },
+            )
+        };
+
+        // Memos are refused until enabled
+        let err = donate_with_memo(&mut deps, "for the docs sprint!").unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMemo {}));
+        let memo_config = ExecuteMsg::SetMemoConfig {
+            config: Some(MemoConfig { max_length: 20 }),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &[]),
+            memo_config.clone(),
+        )
+        .expect_err("only admins can enable memos");
+        execute(deps.as_mut(), mock_env(), owner_info, memo_config)
+            .expect("owner failed to enable memos");
+
+        let err = donate_with_memo(&mut deps, "for the documentation sprint!").unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMemo {}));
+        let res = donate_with_memo(&mut deps, "for the docs sprint!").expect("failed to donate");
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attribute| attribute.key == "memo" && attribute.value == "for the docs sprint!"));
+
+        // The memo shows up in both the donator and the beneficiary histories
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator2", &coins(100, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate without memo");
+        let history: DonationListResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetDonatorDonations {
+                    donator: "donator1".to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(history.donations.len(), 1);
+        assert_eq!(
+            history.donations[0].memo,
+            Some("for the docs sprint!".to_string())
+        );
+        let history: DonationListResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetBeneficiaryDonations {
+                    beneficiary: "beneficiary1".to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(history.donations.len(), 2);
+        assert_eq!(
+            history.donations[0].memo,
+            Some("for the docs sprint!".to_string())
+        );
+        assert_eq!(history.donations[1].memo, None);
     }
+
     #[test]
-    fn funds_distribution_2_beneficiary() {
+    fn test_anonymous_donations() {
         // Instantiating smart contract
         let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
         let msg = InstantiateMsg {
             only_owner_can_register_beneficiary: false,
         };
-        let owner_info = mock_info("owner", &coins(2, "token"));
-        let _res = instantiate(deps.as_mut(), mock_env(), owner_info, msg).unwrap();
+        let _res = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        for beneficiary in ["beneficiary1", "beneficiary2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &[]),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("failed to register beneficiary");
+        }
 
-        // Create two beneficiaries
-        let beneficiary1_info = mock_info("beneficiary1", &coins(1, "token"));
-        let beneficiary2_info = mock_info("beneficiary2", &coins(1, "token"));
+        // Beneficiaries are paid but the donator is only counted in the anonymous bucket
+        let commitment = Sha256::digest(b"donator1secret salt");
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(100, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: Some(AnonymousDonation {
+                    commitment: Some(Binary::from(commitment.as_slice())),
+                }),
+                min_output: None,
+            },
+        )
+        .expect("failed to donate anonymously");
+        assert_eq!(res.messages.len(), 2);
+        let donators: DonatorListResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetAllDonators {}).unwrap())
+                .unwrap();
+        assert!(donators.donators.is_empty());
+        let anonymous: AnonymousDonationsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetAnonymousDonations {},
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(anonymous.donations, coins(100, "token"));
+        let donation: Donation = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetDonation { donation_id: 1 },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(donation.donator, Addr::unchecked(ANONYMOUS_DONATOR));
 
-        // Register beneficiaries
+        // Disclosing needs both the donator address and the salt
+        let disclose = |salt: &str| ExecuteMsg::DiscloseDonation {
+            donation_id: 1,
+            salt: salt.to_string(),
+        };
+        for (sender, salt) in [("donator1", "wrong salt"), ("donator2", "secret salt")] {
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(sender, &[]),
+                disclose(salt),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::InvalidCommitment {}));
+        }
         execute(
             deps.as_mut(),
             mock_env(),
-            beneficiary1_info.clone(),
-            ExecuteMsg::RegisterBeneficiary {},
+            mock_info("donator1", &[]),
+            disclose("secret salt"),
         )
-        .expect("error occured while beneficiary2 tried to register");
+        .expect("failed to disclose donation");
+        let donator: PotDonatorResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetDonator {
+                    donator: "donator1".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(donator.donations, coins(100, "token"));
+        let anonymous: AnonymousDonationsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetAnonymousDonations {},
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(anonymous.donations.is_empty());
         execute(
             deps.as_mut(),
             mock_env(),
-            beneficiary2_info.clone(),
-            ExecuteMsg::RegisterBeneficiary {},
+            mock_info("donator1", &[]),
+            disclose("secret salt"),
         )
-        .expect("error occured while beneficiary2 tried to register");
-
-        // Create one donator
-        let donator1_info = mock_info("donator1", &coins(1000, "token"));
+        .expect_err("a donation can only be disclosed once");
+    }
 
-        // Donate 1000 tokens
-        let _res = execute(
+    #[test]
+    fn test_retract_donation() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let owner_info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+        for beneficiary in ["beneficiary1", "beneficiary2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &[]),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("failed to register beneficiary");
+        }
+        execute(
             deps.as_mut(),
             mock_env(),
-            donator1_info,
-            ExecuteMsg::AddToPot {},
+            owner_info.clone(),
+            ExecuteMsg::SetEpochConfig {
+                config: Some(EpochConfig {
+                    duration: Duration::Height(10),
+                }),
+            },
         )
-        .expect("error occured while donating");
-
-        // Query beneficiary donated funds
-        let res = query(
-            deps.as_ref(),
+        .expect("owner failed to set the epoch config");
+        execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetBeneficiary {
-                beneficiary: beneficiary1_info.sender.to_string(),
+            owner_info,
+            ExecuteMsg::SetGracePeriod {
+                seconds: Some(3600),
             },
         )
-        .expect("could not query beneficiary1 funds");
-        let beneficiary1_funds: BeneficiaryResponse = from_binary(&res).unwrap();
-        assert!(beneficiary1_funds.received_donations[0].amount == Uint128::from(500u32));
+        .expect("owner failed to set the grace period");
+        for (donator, amount) in [("donator1", 100), ("donator1", 100), ("donator2", 50)] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(donator, &coins(amount, "token")),
+                ExecuteMsg::AddToPot {
+                    ibc_origin: None,
+                    memo: None,
+                    anonymous: None,
+                    min_output: None,
+                },
+            )
+            .expect("failed to donate tokens");
+        }
+        let retract = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+                       env: Env,
+                       donator: &str,
+                       donation_id: u64| {
+            execute(
+                deps.as_mut(),
+                env,
+                mock_info(donator, &[]),
+                ExecuteMsg::RetractDonation {
+                    donation_id,
+                    salt: None,
+                },
+            )
+        };
 
-        let res = query(
-            deps.as_ref(),
+        let err = retract(&mut deps, mock_env(), "donator2", 1).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = retract(&mut deps, mock_env(), "donator1", 1).expect("failed to retract");
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "donator1".to_string(),
+                amount: coins(100, "token"),
+            })
+        );
+        let epoch_config: EpochConfigResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetEpochConfig {}).unwrap())
+                .unwrap();
+        assert_eq!(epoch_config.pending_pot, coins(150, "token"));
+        let donator: PotDonatorResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetDonator {
+                    donator: "donator1".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(donator.donations, coins(100, "token"));
+        let err = retract(&mut deps, mock_env(), "donator1", 1).unwrap_err();
+        assert!(matches!(err, ContractError::NotRetractable {}));
+
+        // Anonymous donators retract by opening the commitment of their donation
+        let commitment = Sha256::digest(b"donator3secret salt");
+        execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetBeneficiary {
-                beneficiary: beneficiary2_info.sender.to_string(),
+            mock_info("donator3", &coins(30, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: Some(AnonymousDonation {
+                    commitment: Some(Binary::from(commitment.as_slice())),
+                }),
+                min_output: None,
             },
         )
-        .expect("could not query beneficiary2 funds");
-        let beneficiary2_funds: BeneficiaryResponse = from_binary(&res).unwrap();
-        assert!(beneficiary2_funds.received_donations[0].amount == Uint128::from(500u32));
+        .expect("failed to donate anonymously");
+        let retract_anonymous = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+                                 donator: &str,
+                                 salt: Option<&str>| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(donator, &[]),
+                ExecuteMsg::RetractDonation {
+                    donation_id: 4,
+                    salt: salt.map(|salt| salt.to_string()),
+                },
+            )
+        };
+        let err = retract_anonymous(&mut deps, "donator3", None).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = retract_anonymous(&mut deps, "donator2", Some("secret salt")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidCommitment {}));
+        let res = retract_anonymous(&mut deps, "donator3", Some("secret salt"))
+            .expect("failed to retract the anonymous donation");
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "donator3".to_string(),
+                amount: coins(30, "token"),
+            })
+        );
+        let anonymous: AnonymousDonationsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetAnonymousDonations {},
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(anonymous.donations.is_empty());
+
+        // The grace period is over
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(3600);
+        let err = retract(&mut deps, env, "donator1", 2).unwrap_err();
+        assert!(matches!(err, ContractError::NotRetractable {}));
+
+        // Distributed donations can't be retracted anymore
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::Distribute {},
+        )
+        .expect("failed to distribute");
+        let err = retract(&mut deps, mock_env(), "donator2", 3).unwrap_err();
+        assert!(matches!(err, ContractError::NotRetractable {}));
     }
+
     #[test]
-    fn test_only_admin_can_add_beneficiaries() {
+    fn test_freeze_beneficiary() {
         // Instantiating smart contract
         let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
         let msg = InstantiateMsg {
-            only_owner_can_register_beneficiary: true,
+            only_owner_can_register_beneficiary: false,
         };
-        let owner_info = mock_info("owner", &coins(2, "token"));
+        let owner_info = mock_info("owner", &[]);
         let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
+        for beneficiary in ["beneficiary1", "beneficiary2", "beneficiary3"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &[]),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("failed to register beneficiary");
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary3", &[]),
+            ExecuteMsg::SetRemoteAccount {
+                beneficiary: None,
+                remote: Some(RemoteAccount {
+                    channel_id: "channel-0".to_string(),
+                    address: "osmo1beneficiary3".to_string(),
+                }),
+            },
+        )
+        .expect("failed to set remote account");
 
-        // Create one beneficiary
-        let beneficiary_info = mock_info("beneficiary1", &coins(1, "token"));
+        // The transfers to beneficiary3 fail, leaving 30 + 60 claimable
+        for (donator, amount) in [("donator1", 90), ("donator2", 180)] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(donator, &coins(amount, "token")),
+                ExecuteMsg::AddToPot {
+                    ibc_origin: None,
+                    memo: None,
+                    anonymous: None,
+                    min_output: None,
+                },
+            )
+            .expect("failed to donate tokens");
+            reply(
+                deps.as_mut(),
+                mock_env(),
+                Reply {
+                    id: IBC_TRANSFER_REPLY_ID,
+                    result: SubMsgResult::Err("channel closed".to_string()),
+                },
+            )
+            .expect("failed to handle the transfer reply");
+        }
 
-        // Register beneficiaries as user (should be failing)
-        let _res = execute(
+        let freeze = ExecuteMsg::FreezeBeneficiary {
+            beneficiary: "beneficiary3".to_string(),
+            reason: "impersonating a project".to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &[]),
+            freeze.clone(),
+        )
+        .expect_err("only admins can freeze beneficiaries");
+        execute(deps.as_mut(), mock_env(), owner_info.clone(), freeze)
+            .expect("owner failed to freeze beneficiary");
+
+        // Frozen beneficiaries can't claim nor leave, and are left out of distributions
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("beneficiary3", &[]),
+            ExecuteMsg::ClaimPayouts {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::BeneficiaryFrozen {}));
+        let err = execute(
             deps.as_mut(),
             mock_env(),
-            beneficiary_info.clone(),
-            ExecuteMsg::RegisterBeneficiary {},
+            mock_info("beneficiary3", &[]),
+            ExecuteMsg::RemoveBeneficiary {},
         )
-        .expect_err("should be Unauthorized");
+        .unwrap_err();
+        assert!(matches!(err, ContractError::BeneficiaryFrozen {}));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(100, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
+            },
+        )
+        .expect("failed to donate tokens");
+        assert_eq!(res.messages.len(), 2);
 
-        // Register beneficiaries as owner (should be working)
-        let _res = execute(
+        let resolve = ExecuteMsg::ResolveFreeze {
+            freeze_id: 1,
+            resolution: FreezeResolution::ReturnToDonators,
+        };
+        let err = execute(
             deps.as_mut(),
             mock_env(),
             owner_info.clone(),
-            ExecuteMsg::RegisterBeneficiaryAsOwner {
-                beneficiary: beneficiary_info.sender.clone().to_string(),
-            },
+            resolve.clone(),
         )
-        .expect("owner failed to register beneficiary1 as a beneficiary");
+        .unwrap_err();
+        assert!(matches!(err, ContractError::DisputeNotOver {}));
 
-        // Create one donator
-        let donator1_info = mock_info("donator1", &coins(1000, "token"));
+        // Once the dispute period is over, the balance goes back to the donators pro rata, a page
+        // at a time
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(DEFAULT_DISPUTE_PERIOD);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            owner_info.clone(),
+            resolve.clone(),
+        )
+        .expect("owner failed to resolve the freeze");
+        let refund_donators = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("anyone", &[]),
+                ExecuteMsg::RefundDonators {
+                    freeze_id: 1,
+                    limit: Some(1),
+                },
+            )
+        };
+        for (donator, amount) in [("donator1", 30), ("donator2", 60)] {
+            let res = refund_donators(&mut deps).expect("failed to refund donators");
+            assert_eq!(
+                res.messages[0].msg,
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: donator.to_string(),
+                    amount: coins(amount, "token"),
+                })
+            );
+        }
+        let res = refund_donators(&mut deps).expect("failed to refund donators");
+        assert!(res.messages.is_empty());
+        let err = refund_donators(&mut deps).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToRefund {}));
+        let freeze: Freeze = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetFreeze { freeze_id: 1 },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(freeze.held, coins(90, "token"));
+        assert_eq!(freeze.settled, coins(90, "token"));
+        assert_eq!(freeze.resolution, Some(FreezeResolution::ReturnToDonators));
+        let beneficiaries: BeneficiaryListResponse = from_binary(
+            &query(deps.as_ref(), mock_env(), QueryMsg::GetAllBeneficiaries {}).unwrap(),
+        )
+        .unwrap();
+        assert!(!beneficiaries
+            .beneficiaries
+            .contains(&Addr::unchecked("beneficiary3")));
+        let err = execute(deps.as_mut(), env, owner_info.clone(), resolve).unwrap_err();
+        assert!(matches!(err, ContractError::FreezeResolved {}));
 
-        // Donate 1000 tokens
-        let _res = execute(
+        // The round match of a beneficiary frozen during the round is held as well
+        let start = mock_env().block.time;
+        let end = start.plus_seconds(100);
+        execute(
             deps.as_mut(),
             mock_env(),
-            donator1_info,
-            ExecuteMsg::AddToPot {},
+            mock_info("owner", &coins(100, "token")),
+            ExecuteMsg::CreateRound {
+                start,
+                end,
+                denom: "token".to_string(),
+            },
         )
-        .expect("failed to donate tokens");
-
-        // Query beneficiary donated funds
-        let res = query(
-            deps.as_ref(),
+        .expect("failed to create round");
+        for (donator, beneficiary) in [("donator1", "beneficiary1"), ("donator2", "beneficiary2")] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(donator, &coins(25, "token")),
+                ExecuteMsg::ContributeToRound {
+                    round_id: 1,
+                    beneficiary: beneficiary.to_string(),
+                },
+            )
+            .expect("failed to contribute to round");
+        }
+        execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetBeneficiary {
-                beneficiary: beneficiary_info.sender.to_string(),
+            owner_info,
+            ExecuteMsg::FreezeBeneficiary {
+                beneficiary: "beneficiary2".to_string(),
+                reason: "under review".to_string(),
             },
         )
-        .expect("could not query beneficiary1 funds");
-        let beneficiary1_funds: BeneficiaryResponse = from_binary(&res).unwrap();
-        assert!(beneficiary1_funds.received_donations[0].amount == Uint128::from(1000u32));
+        .expect("owner failed to freeze beneficiary");
+        let simulation: SimulateDonationResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::SimulateDonation {
+                    funds: coins(100, "token"),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            simulation.payouts,
+            vec![Payout {
+                beneficiary: Addr::unchecked("beneficiary1"),
+                amount: coins(100, "token"),
+            }]
+        );
+        let mut env = mock_env();
+        env.block.time = end;
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::FinalizeRound { round_id: 1 },
+        )
+        .expect("failed to finalize round");
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "beneficiary1".to_string(),
+                amount: coins(50, "token"),
+            })]
+        );
+        let claimable: ClaimableResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetClaimable {
+                    beneficiary: "beneficiary2".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(claimable.claimable, coins(50, "token"));
     }
+
     #[test]
-    fn test_only_admin_or_beneficiary_can_remove_beneficiary() {
+    fn test_sweep_unaccounted_balance() {
         // Instantiating smart contract
         let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
         let msg = InstantiateMsg {
             only_owner_can_register_beneficiary: false,
         };
-        let owner_info = mock_info("owner", &coins(2, "token"));
+        let owner_info = mock_info("owner", &[]);
         let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
-
-        // Create two beneficiaries
-        let beneficiary1_info = mock_info("beneficiary1", &coins(1, "token"));
-        let beneficiary2_info = mock_info("beneficiary2", &coins(1, "token"));
-
-        // Register beneficiaries as user
-        let _res = execute(
+        execute(
             deps.as_mut(),
             mock_env(),
-            beneficiary1_info.clone(),
+            mock_info("beneficiary1", &[]),
             ExecuteMsg::RegisterBeneficiary {},
         )
-        .expect("failed to add beneficiary1 as beneficiary");
-        let _res = execute(
+        .expect("failed to register beneficiary");
+        execute(
             deps.as_mut(),
             mock_env(),
-            beneficiary2_info.clone(),
-            ExecuteMsg::RegisterBeneficiary {},
-        )
-        .expect("failed to add beneficiary2 as beneficiary");
-
-        // Check that both beneficiaries are actually registered
-        query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::GetBeneficiary {
-                beneficiary: beneficiary1_info.sender.to_string(),
-            },
-        )
-        .expect("beneficiary1 should be a beneficiary");
-        query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::GetBeneficiary {
-                beneficiary: beneficiary2_info.sender.to_string(),
+            mock_info("beneficiary1", &[]),
+            ExecuteMsg::SetDenomPreference {
+                beneficiary: None,
+                preference: Some(DenomPreference::Accept {
+                    denoms: vec!["token".to_string()],
+                }),
             },
         )
-        .expect("beneficiary2 should be a beneficiary");
-
-        // Non-owner should not be able to call "remove_beneficiary_as_owner"
-        let _res = execute(
+        .expect("failed to set denom preference");
+        execute(
             deps.as_mut(),
             mock_env(),
-            beneficiary1_info.clone(),
-            ExecuteMsg::RemoveBeneficiaryAsOwner {
-                beneficiary: beneficiary2_info.sender.clone().to_string(),
+            owner_info.clone(),
+            ExecuteMsg::SetEpochConfig {
+                config: Some(EpochConfig {
+                    duration: Duration::Height(10),
+                }),
             },
         )
-        .expect_err("should be Unauthorized");
-
-        // Non-owner should be able to remove itself
-        let _res = execute(
+        .expect("owner failed to set the epoch config");
+        execute(
             deps.as_mut(),
             mock_env(),
-            beneficiary1_info.clone(),
-            ExecuteMsg::RemoveBeneficiary {},
-        )
-        .expect("beneficiary1 should be able to remove itself from beneficiaries");
-        query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::GetBeneficiary {
-                beneficiary: beneficiary1_info.sender.to_string(),
+            mock_info("donator1", &coins(100, "token")),
+            ExecuteMsg::AddToPot {
+                ibc_origin: None,
+                memo: None,
+                anonymous: None,
+                min_output: None,
             },
         )
-        .expect_err("beneficiary1 should not be a beneficiary anymore");
-        query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::GetRemovedBeneficiary {
-                beneficiary: beneficiary1_info.sender.to_string(),
-            },
+        .expect("failed to donate tokens");
+
+        // The pending pot is held, the rest was sent directly to the contract
+        deps.querier.update_balance(
+            MOCK_CONTRACT_ADDR,
+            vec![Coin::new(130, "token"), Coin::new(5, "other")],
+        );
+        let res: UnaccountedBalanceResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetUnaccountedBalance {},
+            )
+            .unwrap(),
         )
-        .expect("beneficiary1 should be in removed beneficiaries");
+        .unwrap();
+        assert_eq!(res.held, coins(100, "token"));
+        assert_eq!(
+            res.unaccounted,
+            vec![Coin::new(30, "token"), Coin::new(5, "other")]
+        );
+        // The unaccounted tokens can still be distributed to beneficiary1
+        assert_eq!(res.sweepable, coins(5, "other"));
 
-        // Owner should be able to remove anyone
-        let _res = execute(
+        let err = execute(
             deps.as_mut(),
             mock_env(),
             owner_info.clone(),
-            ExecuteMsg::RemoveBeneficiaryAsOwner {
-                beneficiary: beneficiary2_info.sender.clone().to_string(),
-            },
-        )
-        .expect("owner should be able to remove beneficiary2");
-        query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::GetBeneficiary {
-                beneficiary: beneficiary2_info.sender.to_string(),
+            ExecuteMsg::Sweep {
+                denom: "token".to_string(),
+                to: "treasury".to_string(),
             },
         )
-        .expect_err("beneficiary2 should not be a beneficiary anymore");
-
-        // Both beneficiaries should be in the removed beneficiaries list
-        let res = query(
-            deps.as_ref(),
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToSweep {}));
+        let sweep = ExecuteMsg::Sweep {
+            denom: "other".to_string(),
+            to: "treasury".to_string(),
+        };
+        execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetAllRemovedBeneficiaries {},
+            mock_info("donator1", &[]),
+            sweep.clone(),
         )
-        .expect("failed fetching beneficiairy list");
-        let removed_beneficiaries_list: BeneficiaryListResponse = from_binary(&res).unwrap();
-        assert_eq!(removed_beneficiaries_list.beneficiaries.len(), 2);
-        assert_eq!(
-            removed_beneficiaries_list.beneficiaries[0],
-            beneficiary1_info.sender
-        );
+        .expect_err("only admins can sweep");
+        let res = execute(deps.as_mut(), mock_env(), owner_info.clone(), sweep)
+            .expect("owner failed to sweep");
         assert_eq!(
-            removed_beneficiaries_list.beneficiaries[1],
-            beneficiary2_info.sender
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "treasury".to_string(),
+                amount: coins(5, "other"),
+            })
         );
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::Sweep {
+                denom: "missing".to_string(),
+                to: "treasury".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToSweep {}));
     }
+
     #[test]
-    fn test_split_between_100_beneficiaries() {
+    fn test_leaderboards() {
         // Instantiating smart contract
         let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
         let msg = InstantiateMsg {
             only_owner_can_register_beneficiary: false,
         };
-        let owner_info = mock_info("owner", &coins(2, "token"));
-        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
-
-        // Create 100 beneficiaries
-        let mut beneficiaries_infos: Vec<MessageInfo> = Vec::new();
-        for i in 1..100 {
-            beneficiaries_infos.push(mock_info(&format!("beneficiary{}", i), &coins(1, "token")))
-        }
-        // Register each as a beneficiary
-        for beneficiary_info in &beneficiaries_infos {
+        let _res = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        for beneficiary in ["beneficiary1", "beneficiary2"] {
             execute(
                 deps.as_mut(),
                 mock_env(),
-                beneficiary_info.clone(),
+                mock_info(beneficiary, &[]),
                 ExecuteMsg::RegisterBeneficiary {},
             )
-            .expect("error occured while beneficiary tried to register");
+            .expect("failed to register beneficiary");
         }
-
-        // Donate funds
-        let donator_infos = mock_info("generous_donator", &coins(4500, "token"));
         execute(
             deps.as_mut(),
             mock_env(),
-            donator_infos.clone(),
-            ExecuteMsg::AddToPot {},
+            mock_info("beneficiary2", &[]),
+            ExecuteMsg::SetDenomPreference {
+                beneficiary: None,
+                preference: Some(DenomPreference::Exclude {
+                    denoms: vec!["other".to_string()],
+                }),
+            },
         )
-        .expect("donation failed");
-
-        // Each beneficiary should have 45 tokens
-        for beneficiary_info in &beneficiaries_infos {
-            let beneficiary_query_resp = query(
-                deps.as_ref(),
+        .expect("failed to set denom preference");
+        for (donator, funds) in [
+            ("donator1", coins(100, "token")),
+            ("donator2", coins(300, "token")),
+            ("donator3", coins(200, "token")),
+            ("donator1", coins(150, "token")),
+            ("donator3", coins(1000, "other")),
+        ] {
+            execute(
+                deps.as_mut(),
                 mock_env(),
-                QueryMsg::GetBeneficiary {
-                    beneficiary: beneficiary_info.sender.to_string(),
+                mock_info(donator, &funds),
+                ExecuteMsg::AddToPot {
+                    ibc_origin: None,
+                    memo: None,
+                    anonymous: None,
+                    min_output: None,
                 },
             )
-            .expect("query funds of beneficiary failed");
-            let beneficiary_funds: BeneficiaryResponse =
-                from_binary(&beneficiary_query_resp).unwrap();
-            assert_eq!(
-                beneficiary_funds.received_donations[0].amount,
-                Uint128::from(45u32)
-            );
+            .expect("failed to donate tokens");
         }
-    }
-    #[test]
-    fn test_tracking_of_deleted_and_restored_beneficiaries() {
-        // Instantiating smart contract
-        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
-        let msg = InstantiateMsg {
-            only_owner_can_register_beneficiary: false,
-        };
-        let owner_info = mock_info("owner", &coins(2, "token"));
-        let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
 
-        // Create one beneficiary
-        let beneficiary_info = mock_info("beneficiary1", &coins(1, "token"));
-
-        // Register beneficiary
-        let _res = execute(
-            deps.as_mut(),
-            mock_env(),
-            beneficiary_info.clone(),
-            ExecuteMsg::RegisterBeneficiary {},
+        // Lifetime totals per denom, highest first
+        let top: LeaderboardResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetTopDonators {
+                    denom: "token".to_string(),
+                    limit: Some(2),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            top.entries,
+            vec![
+                LeaderboardEntry {
+                    address: Addr::unchecked("donator2"),
+                    denom: "token".to_string(),
+                    amount: Uint128::new(300),
+                },
+                LeaderboardEntry {
+                    address: Addr::unchecked("donator1"),
+                    denom: "token".to_string(),
+                    amount: Uint128::new(250),
+                },
+            ]
+        );
+        let top: LeaderboardResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetTopBeneficiaries {
+                    denom: "other".to_string(),
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            top.entries,
+            vec![LeaderboardEntry {
+                address: Addr::unchecked("beneficiary1"),
+                denom: "other".to_string(),
+                amount: Uint128::new(1000),
+            }]
+        );
+        let top: LeaderboardResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetTopBeneficiaries {
+                    denom: "token".to_string(),
+                    limit: None,
+                },
+            )
+            .unwrap(),
         )
-        .expect("register beneficiary failed");
+        .unwrap();
+        assert_eq!(top.entries.len(), 2);
+        assert_eq!(top.entries[0].amount, Uint128::new(375));
+    }
 
-        // Donate 1000 tokens
-        let donator1_info = mock_info("donator1", &coins(1000, "token"));
-        let _res = execute(
+    #[test]
+    fn test_stats() {
+        // Instantiating smart contract
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        let msg = InstantiateMsg {
+            only_owner_can_register_beneficiary: false,
+        };
+        let _res = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        execute(
             deps.as_mut(),
             mock_env(),
-            donator1_info,
-            ExecuteMsg::AddToPot {},
-        )
-        .expect("failed to donate tokens");
-
-        // Assert that funds have been correctly donated
-        let beneficiary_query_resp = query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::GetBeneficiary {
-                beneficiary: beneficiary_info.sender.to_string(),
+            mock_info("owner", &[]),
+            ExecuteMsg::SetRegistrationConfig {
+                config: RegistrationConfig {
+                    bond: None,
+                    fee: Some(Coin::new(10, "token")),
+                    duration: None,
+                },
             },
         )
-        .expect("query funds of beneficiary failed");
-        let beneficiary_funds: BeneficiaryResponse = from_binary(&beneficiary_query_resp).unwrap();
-        let total_funds: Uint128 = beneficiary_funds
-            .received_donations
-            .iter()
-            .map(|funds| funds.amount)
-            .sum();
-        assert_eq!(total_funds, Uint128::from(1000u32));
-
-        // Remove beneficiary
-        let _res = execute(
+        .expect("owner failed to set the registration config");
+        for beneficiary in ["beneficiary1", "beneficiary2", "beneficiary3"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(beneficiary, &coins(10, "token")),
+                ExecuteMsg::RegisterBeneficiary {},
+            )
+            .expect("failed to register beneficiary");
+        }
+        execute(
             deps.as_mut(),
             mock_env(),
-            beneficiary_info.clone(),
+            mock_info("beneficiary3", &[]),
             ExecuteMsg::RemoveBeneficiary {},
         )
-        .expect("removing beneficiary failed");
+        .expect("failed to remove beneficiary");
+        for (donator, amount) in [("donator1", 100), ("donator2", 101), ("donator1", 50)] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(donator, &coins(amount, "token")),
+                ExecuteMsg::AddToPot {
+                    ibc_origin: None,
+                    memo: None,
+                    anonymous: None,
+                    min_output: None,
+                },
+            )
+            .expect("failed to donate tokens");
+        }
 
-        // Funds should be in removed beneficiaries
-        let removed_beneficiary_query_resp = query(
-            deps.as_ref(),
+        // The contract is left with the fees and the rounding dust
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(31, "token"));
+        let stats: StatsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetStats {}).unwrap()).unwrap();
+        assert_eq!(
+            stats,
+            StatsResponse {
+                donated: coins(251, "token"),
+                paid_out: coins(250, "token"),
+                fees_collected: coins(30, "token"),
+                dust: coins(1, "token"),
+                donators: 2,
+                registered_beneficiaries: 2,
+                active_beneficiaries: 2,
+                removed_beneficiaries: 1,
+            }
+        );
+
+        // Expired and frozen beneficiaries are still registered, but no longer active
+        execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetRemovedBeneficiary {
-                beneficiary: beneficiary_info.sender.to_string(),
+            mock_info("owner", &[]),
+            ExecuteMsg::SetRegistrationConfig {
+                config: RegistrationConfig {
+                    bond: None,
+                    fee: None,
+                    duration: Some(Duration::Time(100)),
+                },
             },
         )
-        .expect("query funds of removed beneficiary failed");
-        let removed_beneficiary_funds: BeneficiaryResponse =
-            from_binary(&removed_beneficiary_query_resp).unwrap();
-        let total_funds: Uint128 = removed_beneficiary_funds
-            .received_donations
-            .iter()
-            .map(|funds| funds.amount)
-            .sum();
-        assert_eq!(total_funds, Uint128::from(1000u32));
-
-        // Register beneficiary again
-        let _res = execute(
+        .expect("owner failed to set the registration config");
+        execute(
             deps.as_mut(),
             mock_env(),
-            beneficiary_info.clone(),
+            mock_info("beneficiary4", &[]),
             ExecuteMsg::RegisterBeneficiary {},
         )
-        .expect("register beneficiary failed");
-
-        // Assert that removed_beneficiaries is empty
-        let _res = query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::GetRemovedBeneficiary {
-                beneficiary: beneficiary_info.sender.to_string(),
-            },
-        )
-        .expect_err("beneficiary should not be in removed beneficiaries");
-
-        // Check that funds logs have been restored
-        let beneficiary_query_resp = query(
-            deps.as_ref(),
+        .expect("failed to register beneficiary");
+        execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetBeneficiary {
-                beneficiary: beneficiary_info.sender.to_string(),
+            mock_info("owner", &[]),
+            ExecuteMsg::FreezeBeneficiary {
+                beneficiary: "beneficiary1".to_string(),
+                reason: "under review".to_string(),
             },
         )
-        .expect("query funds of beneficiary failed");
-        let beneficiary_funds: BeneficiaryResponse = from_binary(&beneficiary_query_resp).unwrap();
-        let total_funds: Uint128 = beneficiary_funds
-            .received_donations
-            .iter()
-            .map(|funds| funds.amount)
-            .sum();
-        assert_eq!(total_funds, Uint128::from(1000u32));
+        .expect("owner failed to freeze beneficiary");
+        let stats: StatsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetStats {}).unwrap()).unwrap();
+        assert_eq!(stats.registered_beneficiaries, 3);
+        assert_eq!(stats.active_beneficiaries, 2);
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(200);
+        let stats: StatsResponse =
+            from_binary(&query(deps.as_ref(), env, QueryMsg::GetStats {}).unwrap()).unwrap();
+        assert_eq!(stats.registered_beneficiaries, 3);
+        assert_eq!(stats.active_beneficiaries, 1);
+    }
 
-        // Donate 500 tokens
-        let donator1_info = mock_info("donator1", &coins(500, "token"));
-        let _res = execute(
-            deps.as_mut(),
-            mock_env(),
-            donator1_info,
-            ExecuteMsg::AddToPot {},
-        )
-        .expect("failed to donate tokens");
+    #[test]
+    fn test_migration_from_0_4() {
+        // State left by 0.4.1, which only kept the totals
+        let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.4.1").unwrap();
+        STATE
+            .save(
+                deps.as_mut().storage,
+                &State {
+                    owner: Addr::unchecked("owner"),
+                    only_owner_can_register_beneficiary: false,
+                    distribution_strategy: DistributionStrategy::Equal,
+                },
+            )
+            .unwrap();
+        DONATORS
+            .save(
+                deps.as_mut().storage,
+                Addr::unchecked("donator1"),
+                &vec![Coin::new(100, "token"), Coin::new(50, "token")],
+            )
+            .unwrap();
+        BENEFICIARIES
+            .save(
+                deps.as_mut().storage,
+                Addr::unchecked("beneficiary1"),
+                &coins(150, "token"),
+            )
+            .unwrap();
 
-        // Check that funds have been received properly
-        let beneficiary_query_resp = query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::GetBeneficiary {
-                beneficiary: beneficiary_info.sender.to_string(),
-            },
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).expect("failed to migrate");
+        assert_eq!(
+            get_contract_version(&deps.storage).unwrap().version,
+            CONTRACT_VERSION
+        );
+        let top: LeaderboardResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetTopDonators {
+                    denom: "token".to_string(),
+                    limit: None,
+                },
+            )
+            .unwrap(),
         )
-        .expect("query funds of beneficiary failed");
-        let beneficiary_funds: BeneficiaryResponse = from_binary(&beneficiary_query_resp).unwrap();
-        let total_funds: Uint128 = beneficiary_funds
-            .received_donations
-            .iter()
-            .map(|funds| funds.amount)
-            .sum();
-        assert_eq!(total_funds, Uint128::from(1500u32));
-
-        // Remove beneficiary again (as owner)
-        let _res = execute(
+        .unwrap();
+        assert_eq!(
+            top.entries,
+            vec![LeaderboardEntry {
+                address: Addr::unchecked("donator1"),
+                denom: "token".to_string(),
+                amount: Uint128::new(150),
+            }]
+        );
+        let stats: StatsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetStats {}).unwrap()).unwrap();
+        assert_eq!(stats.donated, coins(150, "token"));
+        assert_eq!(stats.paid_out, coins(150, "token"));
+        assert_eq!(stats.donators, 1);
+        assert_eq!(stats.registered_beneficiaries, 1);
+        // Governance weights are only kept once governance is enabled, from the existing totals
+        let height = mock_env().block.height + 1;
+        let donators = VoterSet::Donators {
+            denom: "token".to_string(),
+        };
+        assert!(total_voting_weight(&deps.storage, &donators, height)
+            .unwrap()
+            .is_zero());
+        execute(
             deps.as_mut(),
             mock_env(),
-            owner_info.clone(),
-            ExecuteMsg::RemoveBeneficiaryAsOwner {
-                beneficiary: beneficiary_info.sender.to_string(),
-            },
-        )
-        .expect("removing beneficiary as owner failed");
-
-        // Query one last time from removed beneficiaries
-        let removed_beneficiary_query_resp = query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::GetRemovedBeneficiary {
-                beneficiary: beneficiary_info.sender.to_string(),
+            mock_info("owner", &[]),
+            ExecuteMsg::SetGovernanceConfig {
+                config: Some(GovernanceConfig {
+                    voters: donators.clone(),
+                    quorum: Decimal::percent(50),
+                    threshold: Decimal::percent(50),
+                    voting_period: 100,
+                }),
             },
         )
-        .expect("query funds of removed beneficiary failed");
-        let removed_beneficiary_funds: BeneficiaryResponse =
-            from_binary(&removed_beneficiary_query_resp).unwrap();
-        let total_funds: Uint128 = removed_beneficiary_funds
-            .received_donations
-            .iter()
-            .map(|funds| funds.amount)
-            .sum();
-        assert_eq!(total_funds, Uint128::from(1500u32));
+        .expect("owner failed to enable governance");
+        assert_eq!(
+            voting_weight(
+                &deps.storage,
+                &donators,
+                &Addr::unchecked("donator1"),
+                height
+            )
+            .unwrap(),
+            Uint128::new(150)
+        );
+        assert_eq!(
+            total_voting_weight(&deps.storage, &VoterSet::Beneficiaries, height).unwrap(),
+            Uint128::new(1)
+        );
     }
+
     #[test]
-    fn test_donations_are_not_received_by_removed_beneficiaries() {
+    fn test_pause() {
         // Instantiating smart contract
         let mut deps = mock_dependencies_with_balance(&coins(0, "token"));
         let msg = InstantiateMsg {
             only_owner_can_register_beneficiary: false,
         };
-        let owner_info = mock_info("owner", &coins(2, "token"));
+        let owner_info = mock_info("owner", &[]);
         let _res = instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
-
-        // Create two beneficiaries
-        let beneficiary1_info = mock_info("beneficiary1", &coins(1, "token"));
-        let beneficiary2_info = mock_info("beneficiary2", &coins(1, "token"));
-
-        // Register beneficiaries
-        let _res = execute(
+        execute(
             deps.as_mut(),
             mock_env(),
-            beneficiary1_info.clone(),
+            mock_info("beneficiary1", &[]),
             ExecuteMsg::RegisterBeneficiary {},
         )
-        .expect("register beneficiary1 failed");
-        let _res = execute(
+        .expect("failed to register beneficiary");
+
+        // Only pausers can pause
+        execute(
             deps.as_mut(),
             mock_env(),
-            beneficiary2_info.clone(),
-            ExecuteMsg::RegisterBeneficiary {},
+            mock_info("pauser", &[]),
+            ExecuteMsg::Pause {},
         )
-        .expect("register beneficiary failed");
-
-        // Remove beneficiary1
-        let _res = execute(
+        .expect_err("should be Unauthorized");
+        execute(
             deps.as_mut(),
             mock_env(),
-            owner_info.clone(),
-            ExecuteMsg::RemoveBeneficiaryAsOwner {
-                beneficiary: beneficiary1_info.sender.to_string(),
+            owner_info,
+            ExecuteMsg::GrantRole {
+                role: Role::Pauser,
+                address: "pauser".to_string(),
             },
         )
-        .expect("removing beneficiary as owner failed");
-
-        // Donate funds
-        let donator1_info = mock_info("donator1", &coins(500, "token"));
-        let _res = execute(
+        .expect("owner failed to grant role");
+        execute(
             deps.as_mut(),
             mock_env(),
-            donator1_info,
-            ExecuteMsg::AddToPot {},
+            mock_info("pauser", &[]),
+            ExecuteMsg::Pause {},
         )
-        .expect("failed to donate tokens");
+        .expect("pauser failed to pause");
+        let paused: PausedResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetPaused {}).unwrap())
+                .unwrap();
+        assert!(paused.paused);
 
-        // Assert that beneficiary1 received none of the funds
-        let _res = query(
-            deps.as_ref(),
+        // Donations are rejected, the rest still works
+        let donate_msg = ExecuteMsg::AddToPot {
+            ibc_origin: None,
+            memo: None,
+            anonymous: None,
+            min_output: None,
+        };
+        let err = execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetBeneficiary {
-                beneficiary: beneficiary1_info.sender.to_string(),
-            },
+            mock_info("donator1", &coins(100, "token")),
+            donate_msg.clone(),
         )
-        .expect_err("should not be able to get beneficiary1 as a regular one");
-        let removed_beneficiary1_query_resp = query(
-            deps.as_ref(),
+        .expect_err("donations are paused");
+        assert!(matches!(err, ContractError::Paused {}));
+        execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetRemovedBeneficiary {
-                beneficiary: beneficiary1_info.sender.to_string(),
-            },
+            mock_info("beneficiary2", &[]),
+            ExecuteMsg::RegisterBeneficiary {},
         )
-        .expect("query funds of removed beneficiary failed");
-        let removed_beneficiary1_funds: BeneficiaryResponse =
-            from_binary(&removed_beneficiary1_query_resp).unwrap();
-        let total_funds: Uint128 = removed_beneficiary1_funds
-            .received_donations
-            .iter()
-            .map(|funds| funds.amount)
-            .sum();
-        assert_eq!(total_funds, Uint128::from(0u32));
+        .expect("failed to register beneficiary");
 
-        // Check that beneficiary2 received all funds
-        let beneficiary2_query_resp = query(
-            deps.as_ref(),
+        execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetBeneficiary {
-                beneficiary: beneficiary2_info.sender.to_string(),
-            },
+            mock_info("pauser", &[]),
+            ExecuteMsg::Unpause {},
         )
-        .expect("query funds of removed beneficiary failed");
-        let beneficiary2_funds: BeneficiaryResponse =
-            from_binary(&beneficiary2_query_resp).unwrap();
-        let total_funds: Uint128 = beneficiary2_funds
-            .received_donations
-            .iter()
-            .map(|funds| funds.amount)
-            .sum();
-        assert_eq!(total_funds, Uint128::from(500u32));
+        .expect("pauser failed to unpause");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("donator1", &coins(100, "token")),
+            donate_msg,
+        )
+        .expect("failed to donate tokens");
     }
 }
@@ -19,6 +19,112 @@ pub enum ContractError {
     NotABeneficiary {},
     #[error("NoBeneficiaries")]
     NoBeneficiaries {},
+    #[error("InvalidFunds")]
+    InvalidFunds {},
+    #[error("InvalidRoundWindow")]
+    InvalidRoundWindow {},
+    #[error("RoundNotActive")]
+    RoundNotActive {},
+    #[error("RoundNotEnded")]
+    RoundNotEnded {},
+    #[error("RoundAlreadyFinalized")]
+    RoundAlreadyFinalized {},
+    #[error("RoundNotFinalized")]
+    RoundNotFinalized {},
+    #[error("NothingToReclaim")]
+    NothingToReclaim {},
+    #[error("GovernanceDisabled")]
+    GovernanceDisabled {},
+    #[error("InvalidGovernanceConfig")]
+    InvalidGovernanceConfig {},
+    #[error("NotAVoter")]
+    NotAVoter {},
+    #[error("AlreadyVoted")]
+    AlreadyVoted {},
+    #[error("VotingClosed")]
+    VotingClosed {},
+    #[error("VotingNotEnded")]
+    VotingNotEnded {},
+    #[error("ProposalNotOpen")]
+    ProposalNotOpen {},
+    #[error("RoleAlreadyGranted")]
+    RoleAlreadyGranted {},
+    #[error("RoleNotGranted")]
+    RoleNotGranted {},
+    #[error("Paused")]
+    Paused {},
+    #[error("OperationNotReady")]
+    OperationNotReady {},
+    #[error("ProfileFieldTooLong: {field}")]
+    ProfileFieldTooLong { field: String },
+    #[error("TooManyTags")]
+    TooManyTags {},
+    #[error("NoPendingAddressChange")]
+    NoPendingAddressChange {},
+    #[error("RegistrationExpired")]
+    RegistrationExpired {},
+    #[error("InvalidCaps")]
+    InvalidCaps {},
+    #[error("NoEligibleBeneficiaries")]
+    NoEligibleBeneficiaries {},
+    #[error("InvalidDenomPreference")]
+    InvalidDenomPreference {},
+    #[error("EpochNotEnded")]
+    EpochNotEnded {},
+    #[error("NothingToDistribute")]
+    NothingToDistribute {},
+    #[error("InvalidSwapConfig")]
+    InvalidSwapConfig {},
+    #[error("InvalidEndowmentConfig")]
+    InvalidEndowmentConfig {},
+    #[error("InvalidValidator")]
+    InvalidValidator {},
+    #[error("InvalidRemoteAccount")]
+    InvalidRemoteAccount {},
+    #[error("InvalidIbcTimeout")]
+    InvalidIbcTimeout {},
+    #[error("NothingToClaim")]
+    NothingToClaim {},
+    #[error("NothingToSweep")]
+    NothingToSweep {},
+    #[error("BeneficiaryFrozen")]
+    BeneficiaryFrozen {},
+    #[error("FreezeResolved")]
+    FreezeResolved {},
+    #[error("NothingToRefund")]
+    NothingToRefund {},
+    #[error("DisputeNotOver")]
+    DisputeNotOver {},
+    #[error("NotRetractable")]
+    NotRetractable {},
+    #[error("InvalidCommitment")]
+    InvalidCommitment {},
+    #[error("InvalidMemo")]
+    InvalidMemo {},
+    #[error("IbcOriginRequired")]
+    IbcOriginRequired {},
+    #[error("InvalidIbcOrigin")]
+    InvalidIbcOrigin {},
+    #[error("NftNotHeld")]
+    NftNotHeld {},
+    #[error("CollectionNotAllowed")]
+    CollectionNotAllowed {},
+    #[error("InvalidAuction")]
+    InvalidAuction {},
+    #[error("AuctionNotActive")]
+    AuctionNotActive {},
+    #[error("AuctionNotEnded")]
+    AuctionNotEnded {},
+    #[error("BidTooLow")]
+    BidTooLow {},
+    #[error("SwapInProgress")]
+    SwapInProgress {},
+    #[error("MinOutputRequired")]
+    MinOutputRequired {},
+    #[error("SwapOutputTooLow")]
+    SwapOutputTooLow {},
+    #[error("UnknownReplyId: {id}")]
+    UnknownReplyId { id: u64 },
     #[error("Semver parsing error: {0}")]
     SemVer(String),
 }
@@ -1,7 +1,15 @@
-use cosmwasm_std::{Addr, Coin};
+use cosmwasm_std::{Addr, Binary, Coin, Delegation, Timestamp, Uint128};
+use cw_utils::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::state::{
+    Auction, BeneficiaryProfile, DenomPreference, DistributionStrategy, Donation, EndowmentConfig,
+    EndowmentLedger, Epoch, EpochConfig, Freeze, FreezeResolution, GovernanceConfig, HeldNft,
+    LeaderboardEntry, MemoConfig, Payout, PendingOperation, Proposal, ProposalAction,
+    ReceiptConfig, RegistrationConfig, RemoteAccount, Role, SwapConfig,
+};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub only_owner_can_register_beneficiary: bool,
@@ -12,23 +20,367 @@ pub struct MigrateMsg {}
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    RegisterBeneficiaryAsOwner { beneficiary: String },
+    RegisterBeneficiaryAsOwner {
+        beneficiary: String,
+    },
     RegisterBeneficiary {},
     RemoveBeneficiary {},
-    RemoveBeneficiaryAsOwner { beneficiary: String },
-    AddToPot {},
+    RemoveBeneficiaryAsOwner {
+        beneficiary: String,
+    },
+    AddToPot {
+        /// Required when relayed by ibc-hooks, to attribute the donation to the sender on the other
+        /// chain. Donations from an ibc-hooks intermediary without it are rejected
+        ibc_origin: Option<IbcOrigin>,
+        /// Public message stored with the donation, only accepted when memos are enabled
+        memo: Option<String>,
+        /// Keeps the donator out of the donators list
+        anonymous: Option<AnonymousDonation>,
+        /// Minimum amount received in the target denom, required when the donation gets swapped
+        min_output: Option<Uint128>,
+    },
+    CreateRound {
+        start: Timestamp,
+        end: Timestamp,
+        denom: String,
+    },
+    FundRound {
+        round_id: u64,
+    },
+    ContributeToRound {
+        round_id: u64,
+        beneficiary: String,
+    },
+    FinalizeRound {
+        round_id: u64,
+    },
+    /// Returns the funder's share of what was left of the matching pool once finalized
+    ReclaimMatchingPool {
+        round_id: u64,
+    },
+    SetGovernanceConfig {
+        config: Option<GovernanceConfig>,
+    },
+    Propose {
+        action: ProposalAction,
+    },
+    Vote {
+        proposal_id: u64,
+        approve: bool,
+    },
+    ExecuteProposal {
+        proposal_id: u64,
+    },
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+    /// Rejects donations and payouts until unpaused
+    Pause {},
+    Unpause {},
+    SetTimelockDelay {
+        delay: u64,
+    },
+    ExecuteOperation {
+        operation_id: u64,
+    },
+    CancelOperation {
+        operation_id: u64,
+    },
+    SetBeneficiaryProfile {
+        beneficiary: Option<String>,
+        profile: Option<BeneficiaryProfile>,
+    },
+    ChangeBeneficiaryAddress {
+        new_address: String,
+    },
+    ConfirmBeneficiaryAddress {
+        old_address: String,
+    },
+    SetRegistrationConfig {
+        config: RegistrationConfig,
+    },
+    WithdrawFees {
+        to: String,
+    },
+    RenewRegistration {},
+    CleanupExpiredBeneficiaries {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    SetBeneficiaryCaps {
+        beneficiary: String,
+        caps: Vec<Coin>,
+    },
+    SetDenomPreference {
+        beneficiary: Option<String>,
+        preference: Option<DenomPreference>,
+    },
+    SetDistributionStrategy {
+        strategy: DistributionStrategy,
+    },
+    SetEpochConfig {
+        config: Option<EpochConfig>,
+    },
+    Distribute {},
+    SetSwapConfig {
+        config: Option<SwapConfig>,
+    },
+    SetEndowmentConfig {
+        config: Option<EndowmentConfig>,
+    },
+    HarvestRewards {},
+    /// Delegates undelegated principal again
+    Delegate {
+        validator: String,
+        amount: Coin,
+    },
+    Redelegate {
+        src_validator: String,
+        dst_validator: String,
+        amount: Coin,
+    },
+    Undelegate {
+        validator: String,
+        amount: Coin,
+    },
+    SetRemoteAccount {
+        beneficiary: Option<String>,
+        remote: Option<RemoteAccount>,
+    },
+    SetIbcTimeout {
+        seconds: u64,
+    },
+    ClaimPayouts {},
+    SetReceiptConfig {
+        config: Option<ReceiptConfig>,
+    },
+    SetMemoConfig {
+        config: Option<MemoConfig>,
+    },
+    /// Attributes an anonymous donation to the sender, given the salt of its commitment
+    DiscloseDonation {
+        donation_id: u64,
+        salt: String,
+    },
+    /// Called by a CW721 collection when an NFT is sent to the contract
+    ReceiveNft(Cw721ReceiveMsg),
+    /// CW721 collections NFTs are accepted from, none by default
+    SetNftCollections {
+        collections: Vec<String>,
+    },
+    /// Auctions an NFT held by the contract
+    StartAuction {
+        collection: String,
+        token_id: String,
+        reserve_price: Coin,
+        duration: u64,
+    },
+    Bid {
+        auction_id: u64,
+    },
+    SettleAuction {
+        auction_id: u64,
+    },
+    /// Distributes the balance not held for anyone, e.g. royalties paid with a bank transfer
+    DistributeRoyalties {},
+    /// Seconds during which accumulated donations can be retracted, unset to disable retractions
+    SetGracePeriod {
+        seconds: Option<u64>,
+    },
+    /// Takes back an accumulated donation not distributed yet, within the grace period
+    RetractDonation {
+        donation_id: u64,
+        /// Salt of the commitment, only to retract an anonymous donation
+        salt: Option<String>,
+    },
+    /// Excludes a beneficiary from distributions and blocks its claimable balance
+    FreezeBeneficiary {
+        beneficiary: String,
+        reason: String,
+    },
+    ResolveFreeze {
+        freeze_id: u64,
+        resolution: FreezeResolution,
+    },
+    /// Refunds the next page of donators of a freeze resolved with `ReturnToDonators`
+    RefundDonators {
+        freeze_id: u64,
+        limit: Option<u32>,
+    },
+    SetDisputePeriod {
+        seconds: u64,
+    },
+    /// Sends the unaccounted balance of a denom, see `GetUnaccountedBalance`
+    Sweep {
+        denom: String,
+        to: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AnonymousDonation {
+    /// sha256 of the donator address followed by a secret salt, allows a later disclosure
+    pub commitment: Option<Binary>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw721ReceiveMsg {
+    pub sender: String,
+    pub token_id: String,
+    pub msg: Binary,
+}
+
+/// Payload of `Cw721ReceiveMsg`, an empty payload holds the NFT
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveNftMsg {
+    Hold {},
+    Auction { reserve_price: Coin, duration: u64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IbcOrigin {
+    /// Channel on this chain the transfer was received from (the destination channel of the
+    /// transfer, not the one it was sent on from the other chain)
+    pub channel: String,
+    /// Sender of the transfer on the other chain
+    pub sender: String,
+}
+
+/// Sent by the chain, ibc-hooks lifecycle callbacks of the transfers to remote beneficiaries
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    IbcLifecycleComplete(IbcLifecycleComplete),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IbcLifecycleComplete {
+    IbcAck {
+        channel: String,
+        sequence: u64,
+        ack: String,
+        success: bool,
+    },
+    IbcTimeout {
+        channel: String,
+        sequence: u64,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetState {},
-    GetDonator { donator: String },
-    GetBeneficiary { beneficiary: String },
-    GetRemovedBeneficiary { beneficiary: String },
+    GetDonator {
+        donator: String,
+    },
+    GetBeneficiary {
+        beneficiary: String,
+    },
+    GetRemovedBeneficiary {
+        beneficiary: String,
+    },
     GetAllDonators {},
     GetAllBeneficiaries {},
     GetAllRemovedBeneficiaries {},
+    GetRound {
+        round_id: u64,
+    },
+    GetRoundMatches {
+        round_id: u64,
+    },
+    GetRoundDonator {
+        round_id: u64,
+        donator: String,
+    },
+    GetRoundBeneficiary {
+        round_id: u64,
+        beneficiary: String,
+    },
+    GetGovernanceConfig {},
+    GetProposal {
+        proposal_id: u64,
+    },
+    GetAllProposals {},
+    GetRoleHolders {},
+    GetPaused {},
+    GetPendingOperations {},
+    GetRegistrationConfig {},
+    GetBond {
+        beneficiary: String,
+    },
+    GetInactiveBeneficiaries {},
+    SimulateDonation {
+        funds: Vec<Coin>,
+    },
+    GetDonation {
+        donation_id: u64,
+    },
+    GetDonations {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetDonatorDonations {
+        donator: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Donations which paid the beneficiary directly, epoch distributions excluded
+    GetBeneficiaryDonations {
+        beneficiary: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetMemoConfig {},
+    GetGracePeriod {},
+    GetUnaccountedBalance {},
+    GetStats {},
+    GetTopDonators {
+        denom: String,
+        limit: Option<u32>,
+    },
+    GetTopBeneficiaries {
+        denom: String,
+        limit: Option<u32>,
+    },
+    GetFreeze {
+        freeze_id: u64,
+    },
+    GetFreezes {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetAnonymousDonations {},
+    GetEpochConfig {},
+    GetSwapConfig {},
+    GetReceiptConfig {},
+    GetAuction {
+        auction_id: u64,
+    },
+    GetAuctions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetHeldNfts {},
+    GetNftCollections {},
+    GetClaimable {
+        beneficiary: String,
+    },
+    GetEndowment {},
+    GetEpoch {
+        epoch_id: u64,
+    },
+    GetEpochs {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -41,6 +393,21 @@ pub struct PotDonatorResponse {
 pub struct BeneficiaryResponse {
     pub beneficiary: Addr,
     pub received_donations: Vec<Coin>,
+    pub profile: Option<BeneficiaryProfile>,
+    pub expires: Option<Expiration>,
+    pub active: bool,
+    pub caps: Vec<CapStatus>,
+    pub denom_preference: Option<DenomPreference>,
+    pub remote: Option<RemoteAccount>,
+    /// Active freeze, if any
+    pub freeze_id: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CapStatus {
+    pub cap: Coin,
+    pub received: Uint128,
+    pub reached: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -52,3 +419,226 @@ pub struct DonatorListResponse {
 pub struct BeneficiaryListResponse {
     pub beneficiaries: Vec<Addr>,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoundMatch {
+    pub beneficiary: Addr,
+    pub contributions: Uint128,
+    pub projected_match: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoundMatchesResponse {
+    pub round_id: u64,
+    pub matching_pool: Uint128,
+    pub matches: Vec<RoundMatch>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GovernanceConfigResponse {
+    pub config: Option<GovernanceConfig>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalListResponse {
+    pub proposals: Vec<Proposal>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoleHolders {
+    pub role: Role,
+    pub holders: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoleHoldersResponse {
+    pub roles: Vec<RoleHolders>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PausedResponse {
+    pub paused: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOperationsResponse {
+    pub delay: u64,
+    pub operations: Vec<PendingOperation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RegistrationConfigResponse {
+    pub config: RegistrationConfig,
+    pub collected_fees: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BondResponse {
+    pub beneficiary: Addr,
+    pub bond: Option<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateDonationResponse {
+    pub strategy: DistributionStrategy,
+    pub payouts: Vec<Payout>,
+    pub refunded: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DonationListResponse {
+    pub donations: Vec<Donation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochConfigResponse {
+    pub config: Option<EpochConfig>,
+    pub pending_pot: Vec<Coin>,
+    pub next_distribution: Option<Expiration>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochListResponse {
+    pub epochs: Vec<Epoch>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapConfigResponse {
+    pub config: Option<SwapConfig>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EndowmentResponse {
+    pub config: Option<EndowmentConfig>,
+    pub delegations: Vec<Delegation>,
+    pub ledger: EndowmentLedger,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimableResponse {
+    pub beneficiary: Addr,
+    pub claimable: Vec<Coin>,
+}
+
+/// Message the swap router has to accept, the offered coin is sent along
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapRouterExecuteMsg {
+    Swap {
+        ask_denom: String,
+        min_output: Uint128,
+    },
+}
+
+/// Query the swap router has to answer with a `SimulateSwapResponse`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapRouterQueryMsg {
+    SimulateSwap { offer: Coin, ask_denom: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateSwapResponse {
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AnonymousDonationsResponse {
+    pub donations: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FreezeListResponse {
+    pub freezes: Vec<Freeze>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StatsResponse {
+    pub donated: Vec<Coin>,
+    pub paid_out: Vec<Coin>,
+    pub fees_collected: Vec<Coin>,
+    /// Rounding dust left by distributions and refunds, until swept
+    pub dust: Vec<Coin>,
+    pub donators: u64,
+    /// Beneficiaries not removed yet, see `GetInactiveBeneficiaries` for those expired
+    pub registered_beneficiaries: u64,
+    /// Registered beneficiaries which would take part in a distribution now, neither expired
+    /// nor frozen
+    pub active_beneficiaries: u64,
+    pub removed_beneficiaries: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LeaderboardResponse {
+    /// Highest totals first
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UnaccountedBalanceResponse {
+    /// Bank balance of the contract
+    pub balance: Vec<Coin>,
+    /// Held on behalf of someone: bonds, fees, pending pot, claimable payouts, matching pools,
+    /// escrowed bids, refunds owed to donators and endowment funds, plus the rounding dust
+    pub held: Vec<Coin>,
+    pub unaccounted: Vec<Coin>,
+    /// Rounding dust and the part of the unaccounted balance no beneficiary can receive, the
+    /// only funds Sweep moves
+    pub sweepable: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GracePeriodResponse {
+    pub seconds: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MemoConfigResponse {
+    pub config: Option<MemoConfig>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReceiptConfigResponse {
+    pub config: Option<ReceiptConfig>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuctionListResponse {
+    pub auctions: Vec<Auction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HeldNftListResponse {
+    pub nfts: Vec<HeldNft>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NftCollectionsResponse {
+    pub collections: Vec<Addr>,
+}
+
+/// CW721 execute messages sent by the contract, receipts use `ReceiptMetadata` as extension
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721ExecuteMsg {
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: ReceiptMetadata,
+    },
+    TransferNft {
+        recipient: String,
+        token_id: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReceiptMetadata {
+    pub donation_id: u64,
+    pub amount: Vec<Coin>,
+    pub denoms: Vec<String>,
+    pub timestamp: Timestamp,
+    pub pot: Addr,
+    pub beneficiaries: u32,
+}
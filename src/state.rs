@@ -1,16 +1,586 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Coin};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Binary, Coin, Decimal, Empty, Timestamp, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex, SnapshotMap, Strategy};
+use cw_utils::{Duration, Expiration};
+
+use crate::msg::ExecuteMsg;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
     pub owner: Addr,
     pub only_owner_can_register_beneficiary: bool,
+    #[serde(default)]
+    pub distribution_strategy: DistributionStrategy,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DistributionStrategy {
+    /// Each donation is split equally amongst the beneficiaries
+    #[default]
+    Equal,
+    /// Each donation goes to the next beneficiary after the one paid last
+    RoundRobin,
+    /// Each donation goes to a beneficiary drawn from the block data and donation count
+    Lottery,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Holds every other role and can grant / revoke roles
+    Admin,
+    /// Can register and remove any beneficiary
+    BeneficiaryManager,
+    /// Can change the registration bond / fee and withdraw collected fees
+    FeeManager,
+    /// Can pause and unpause donations and payouts
+    Pauser,
+}
+
+impl Role {
+    pub const ALL: [Role; 4] = [
+        Role::Admin,
+        Role::BeneficiaryManager,
+        Role::FeeManager,
+        Role::Pauser,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::BeneficiaryManager => "beneficiary_manager",
+            Role::FeeManager => "fee_manager",
+            Role::Pauser => "pauser",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct RegistrationConfig {
+    /// Held while registered, refunded on voluntary removal and slashed on removal by a manager
+    pub bond: Option<Coin>,
+    /// Non-refundable, added to the collected fees
+    pub fee: Option<Coin>,
+    /// Registrations expire after this duration unless renewed
+    pub duration: Option<Duration>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct BeneficiaryProfile {
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    pub url: Option<String>,
+    pub logo_uri: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DenomPreference {
+    /// Only receive these denoms
+    Accept { denoms: Vec<String> },
+    /// Receive every denom but these
+    Exclude { denoms: Vec<String> },
+}
+
+impl DenomPreference {
+    pub fn accepts(&self, denom: &str) -> bool {
+        match self {
+            DenomPreference::Accept { denoms } => denoms.iter().any(|d| d == denom),
+            DenomPreference::Exclude { denoms } => !denoms.iter().any(|d| d == denom),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RemoteAccount {
+    /// Transfer channel on this chain
+    pub channel_id: String,
+    /// Address on the remote chain
+    pub address: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IbcTransfer {
+    pub beneficiary: Addr,
+    pub channel_id: String,
+    pub amount: Coin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Payout {
+    pub beneficiary: Addr,
+    pub amount: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Donation {
+    pub id: u64,
+    pub donator: Addr,
+    pub height: u64,
+    pub time: Timestamp,
+    #[serde(default)]
+    pub funds: Vec<Coin>,
+    /// Added to the pending pot, paid out by the next epoch instead
+    #[serde(default)]
+    pub accumulated: bool,
+    /// Delegated, only the staking rewards are paid out
+    #[serde(default)]
+    pub endowed: bool,
+    #[serde(default)]
+    pub strategy: DistributionStrategy,
+    pub payouts: Vec<Payout>,
+    /// Part of the funds no beneficiary could receive, sent back to the donator
+    pub refunded: Vec<Coin>,
+    #[serde(default)]
+    pub swap: Option<SwapRecord>,
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Recorded in the anonymous bucket, the donator is not stored until disclosed
+    #[serde(default)]
+    pub anonymous: bool,
+    #[serde(default)]
+    pub commitment: Option<Binary>,
+    /// Taken back by the donator before being distributed
+    #[serde(default)]
+    pub retracted: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapRecord {
+    /// Donated coins sent to the router
+    pub offered: Vec<Coin>,
+    /// Output of the swaps, the donated target denom excluded
+    pub received: Coin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapConfig {
+    pub target_denom: String,
+    /// Router contract implementing `SwapRouterExecuteMsg` / `SwapRouterQueryMsg`
+    pub router: Addr,
+    /// Maximum difference between the simulated and the actual swap output
+    pub max_slippage: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MemoConfig {
+    /// Maximum length of a memo, in bytes
+    pub max_length: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReceiptConfig {
+    /// CW721 collection the contract is allowed to mint on
+    pub collection: Addr,
+    pub token_uri: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HeldNft {
+    pub collection: Addr,
+    pub token_id: String,
+    /// Who sent the NFT, the proceeds of its sale are donated in their name
+    pub sender: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Bid {
+    pub bidder: Addr,
+    pub amount: Coin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Auction {
+    pub id: u64,
+    pub nft: HeldNft,
+    pub reserve_price: Coin,
+    pub end: Timestamp,
+    /// Escrowed by the contract until the auction is settled or outbid
+    pub highest_bid: Option<Bid>,
+    pub settled: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EndowmentConfig {
+    /// Staking denom, the only one accepted in endowment mode
+    pub denom: String,
+    /// Validator addresses donations are delegated to
+    pub validators: Vec<String>,
+}
+
+/// Endowment funds sitting in the contract balance instead of being delegated
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct EndowmentLedger {
+    /// Rewards withdrawn along with a delegation change, fanned out by the next harvest
+    pub withdrawn_rewards: Vec<Coin>,
+    /// Principal undelegated, unbonding or back in the balance, until it is delegated again
+    pub undelegated: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingSwap {
+    pub donator: Addr,
+    pub offered: Vec<Coin>,
+    /// Target denom amount donated as is
+    pub donated: Uint128,
+    /// Contract balance in the target denom before the swaps
+    pub balance_before: Uint128,
+    /// Swaps whose reply has not been handled yet
+    pub remaining: u32,
+    #[serde(default)]
+    pub options: DonationOptions,
+}
+
+/// Set by the donator along with the funds
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct DonationOptions {
+    pub memo: Option<String>,
+    pub anonymous: bool,
+    /// sha256 of the donator address followed by a secret salt, revealed to disclose the donation
+    pub commitment: Option<Binary>,
+    /// Minimum amount received from the swaps, in the target denom
+    #[serde(default)]
+    pub min_output: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochConfig {
+    /// Minimum time between two distributions
+    pub duration: Duration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Epoch {
+    pub id: u64,
+    pub height: u64,
+    pub time: Timestamp,
+    pub strategy: DistributionStrategy,
+    pub payouts: Vec<Payout>,
+    /// Part of the pending pot left for the next epoch
+    pub carried_over: Vec<Coin>,
+    /// Accumulated donations up to this one were part of the distribution
+    #[serde(default)]
+    pub last_donation_id: u64,
+}
+
+/// Pot-wide totals, maintained as they change
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Stats {
+    /// Donated to the pot, retracted donations excluded
+    pub donated: Vec<Coin>,
+    /// Sent to beneficiaries by distributions
+    pub paid_out: Vec<Coin>,
+    /// Registration fees and slashed bonds
+    pub fees_collected: Vec<Coin>,
+    /// Rounding dust left in the contract by distributions and refunds, until swept
+    pub dust: Vec<Coin>,
+    /// Addresses in DONATORS, anonymous donations aside
+    pub donators: u64,
+    /// In BENEFICIARIES, expired or frozen ones included as expiring is not an event
+    pub registered_beneficiaries: u64,
+    pub removed_beneficiaries: u64,
+}
+
+/// Lifetime total of an address in a denom
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LeaderboardEntry {
+    pub address: Addr,
+    pub denom: String,
+    pub amount: Uint128,
+}
+
+pub struct LeaderboardIndexes<'a> {
+    /// (denom, big-endian amount), integer keys only go up to u64
+    pub amount: MultiIndex<'a, (String, Vec<u8>), LeaderboardEntry, (Addr, String)>,
+}
+
+impl<'a> IndexList<LeaderboardEntry> for LeaderboardIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<LeaderboardEntry>> + '_> {
+        let v: Vec<&dyn Index<LeaderboardEntry>> = vec![&self.amount];
+        Box::new(v.into_iter())
+    }
+}
+
+pub type Leaderboard<'a> = IndexedMap<'a, (Addr, String), LeaderboardEntry, LeaderboardIndexes<'a>>;
+
+fn leaderboard<'a>(namespace: &'a str, amount_namespace: &'a str) -> Leaderboard<'a> {
+    let indexes = LeaderboardIndexes {
+        amount: MultiIndex::new(
+            |entry: &LeaderboardEntry| {
+                (
+                    entry.denom.clone(),
+                    entry.amount.u128().to_be_bytes().to_vec(),
+                )
+            },
+            namespace,
+            amount_namespace,
+        ),
+    };
+    IndexedMap::new(namespace, indexes)
+}
+
+// Lifetime totals of the donators, kept in sync with DONATORS
+pub fn donator_leaderboard<'a>() -> Leaderboard<'a> {
+    leaderboard("donator_leaderboard", "donator_leaderboard__amount")
+}
+
+// Lifetime totals of the beneficiaries, removed ones included
+pub fn beneficiary_leaderboard<'a>() -> Leaderboard<'a> {
+    leaderboard("beneficiary_leaderboard", "beneficiary_leaderboard__amount")
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Freeze {
+    pub id: u64,
+    pub beneficiary: Addr,
+    pub reason: String,
+    pub frozen_at: Timestamp,
+    /// Only a release is possible before
+    pub dispute_end: Timestamp,
+    /// Claimable balance when frozen
+    pub held: Vec<Coin>,
+    pub resolution: Option<FreezeResolution>,
+    pub resolved_at: Option<Timestamp>,
+    /// Claimable balance the resolution applied to
+    pub settled: Vec<Coin>,
+    /// Set when the balance is returned to the donators
+    #[serde(default)]
+    pub refunds: Option<DonatorRefunds>,
+}
+
+/// Donators are refunded a page at a time, in the order of their address
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DonatorRefunds {
+    /// What the refunded donators had paid the beneficiary directly, when the freeze was resolved
+    pub paid: Vec<Coin>,
+    pub refunded: Vec<Coin>,
+    /// The next page starts after this donator
+    pub last_donator: Option<Addr>,
+    /// Rounding dust is kept by the contract once every donator is refunded
+    pub done: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FreezeResolution {
+    /// The beneficiary is cleared and can claim again
+    Release,
+    /// The beneficiary is removed and its balance split amongst the other beneficiaries
+    Redistribute,
+    /// The beneficiary is removed and its balance returned pro rata to the donators who paid it
+    ReturnToDonators,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Round {
+    pub id: u64,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub denom: String,
+    pub matching_pool: Uint128,
+    pub finalized: bool,
+    /// Part of the matching pool no beneficiary was matched with, returned to the funders
+    pub leftover: Uint128,
+    /// Part of the leftover already reclaimed by the funders
+    pub reclaimed: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VoterSet {
+    /// Every beneficiary has a single vote
+    Beneficiaries,
+    /// Donators vote with the total amount they donated in the given denom
+    Donators { denom: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GovernanceConfig {
+    pub voters: VoterSet,
+    /// Minimum share of the total voting weight that has to take part in a vote
+    pub quorum: Decimal,
+    /// Minimum share of "yes" among the casted votes for a proposal to pass
+    pub threshold: Decimal,
+    /// Voting period in seconds
+    pub voting_period: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalAction {
+    RegisterBeneficiary {
+        beneficiary: String,
+    },
+    RemoveBeneficiary {
+        beneficiary: String,
+    },
+    /// Replaces the caps of a beneficiary, an empty list removes them
+    SetBeneficiaryCaps {
+        beneficiary: String,
+        caps: Vec<Coin>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Open,
+    Rejected,
+    Executed,
+    /// Passed, but the action could no longer be applied
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Addr,
+    pub action: ProposalAction,
+    pub voting_ends: Timestamp,
+    // Snapshot of the governance settings when the proposal was made
+    pub voters: VoterSet,
+    /// Votes are weighted as of the start of this block
+    pub height: u64,
+    pub quorum: Decimal,
+    pub threshold: Decimal,
+    pub total_weight: Uint128,
+    pub yes: Uint128,
+    pub no: Uint128,
+    pub status: ProposalStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOperation {
+    pub id: u64,
+    pub proposer: Addr,
+    pub msg: ExecuteMsg,
+    pub executable_at: Timestamp,
 }
 
 pub const STATE: Item<State> = Item::new("state");
 pub const BENEFICIARIES: Map<Addr, Vec<Coin>> = Map::new("beneficiaries");
 pub const REMOVED_BENEFICIARIES: Map<Addr, Vec<Coin>> = Map::new("removed_beneficiaries");
 pub const DONATORS: Map<Addr, Vec<Coin>> = Map::new("donators");
+// Bond / fee asked to beneficiaries registering themselves
+pub const REGISTRATION_CONFIG: Item<RegistrationConfig> = Item::new("registration_config");
+pub const BONDS: Map<Addr, Coin> = Map::new("bonds");
+pub const COLLECTED_FEES: Item<Vec<Coin>> = Item::new("collected_fees");
+// Kept for beneficiaries moved to REMOVED_BENEFICIARIES on expiry so they can renew
+pub const EXPIRATIONS: Map<Addr, Expiration> = Map::new("expirations");
+// Lifetime amount, per denom, after which a beneficiary stops receiving donations
+pub const CAPS: Map<Addr, Vec<Coin>> = Map::new("caps");
+pub const DENOM_PREFERENCES: Map<Addr, DenomPreference> = Map::new("denom_preferences");
+// Beneficiaries paid over IBC
+pub const REMOTE_ACCOUNTS: Map<Addr, RemoteAccount> = Map::new("remote_accounts");
+// Seconds before a transfer to a remote beneficiary times out
+pub const IBC_TIMEOUT: Item<u64> = Item::new("ibc_timeout");
+// Transfers waiting for their reply, in the order they were sent
+pub const IBC_TRANSFER_QUEUE: Item<Vec<IbcTransfer>> = Item::new("ibc_transfer_queue");
+// (channel_id, sequence) => transfer waiting for its acknowledgement
+pub const IBC_TRANSFERS: Map<(&str, u64), IbcTransfer> = Map::new("ibc_transfers");
+// Transfers sent without their sequence being reported, per channel in the order they were sent.
+// They are matched with the callbacks of unknown sequences on their channel
+pub const UNSEQUENCED_TRANSFERS: Map<&str, Vec<IbcTransfer>> = Map::new("unsequenced_transfers");
+// Failed transfers, claimable by the beneficiary on this chain
+pub const CLAIMABLE: Map<Addr, Vec<Coin>> = Map::new("claimable");
+// Kept apart from BENEFICIARIES so profiles survive a removal
+pub const PROFILES: Map<Addr, BeneficiaryProfile> = Map::new("profiles");
+// old address => new address, waiting for the new address to confirm
+pub const PENDING_ADDRESS_CHANGES: Map<Addr, Addr> = Map::new("pending_address_changes");
+// old address => new address, used to resolve queries made with an old address
+pub const BENEFICIARY_FORWARDS: Map<Addr, Addr> = Map::new("beneficiary_forwards");
+// (role, holder), the owner implicitly holds every role
+pub const ROLES: Map<(&str, Addr), Empty> = Map::new("roles");
+// Donations and payouts are rejected while set
+pub const PAUSED: Item<bool> = Item::new("paused");
+
+// History of the donations made to the pot
+pub const DONATION_COUNT: Item<u64> = Item::new("donation_count");
+pub const DONATIONS: Map<u64, Donation> = Map::new("donations");
+// Donation ids by donator and by directly paid beneficiary, for the history queries
+pub const DONATOR_DONATIONS: Map<(Addr, u64), Empty> = Map::new("donator_donations");
+pub const BENEFICIARY_DONATIONS: Map<(Addr, u64), Empty> = Map::new("beneficiary_donations");
+// Totals of the anonymous donations, which are not in DONATORS
+pub const ANONYMOUS_DONATIONS: Item<Vec<Coin>> = Item::new("anonymous_donations");
+// Memos are accepted on donations when set
+pub const MEMO_CONFIG: Item<MemoConfig> = Item::new("memo_config");
+// Accumulation mode, donations are paid out once per epoch when set
+pub const EPOCH_CONFIG: Item<EpochConfig> = Item::new("epoch_config");
+pub const PENDING_POT: Item<Vec<Coin>> = Item::new("pending_pot");
+pub const NEXT_DISTRIBUTION: Item<Expiration> = Item::new("next_distribution");
+pub const EPOCH_COUNT: Item<u64> = Item::new("epoch_count");
+pub const EPOCHS: Map<u64, Epoch> = Map::new("epochs");
+// Accumulated donations can be retracted during this many seconds when set
+pub const GRACE_PERIOD: Item<u64> = Item::new("grace_period");
+// Donations in other denoms are swapped into the target denom when set
+pub const SWAP_CONFIG: Item<SwapConfig> = Item::new("swap_config");
+pub const PENDING_SWAP: Item<PendingSwap> = Item::new("pending_swap");
+// Endowment mode, donations are delegated and only the rewards distributed when set
+pub const ENDOWMENT_CONFIG: Item<EndowmentConfig> = Item::new("endowment_config");
+pub const ENDOWMENT_LEDGER: Item<EndowmentLedger> = Item::new("endowment_ledger");
+// Donation receipts are minted when set
+pub const RECEIPT_CONFIG: Item<ReceiptConfig> = Item::new("receipt_config");
+// CW721 collections NFTs are accepted from
+pub const NFT_COLLECTIONS: Item<Vec<Addr>> = Item::new("nft_collections");
+// NFTs received and not auctioned, keyed by (collection, token id)
+pub const HELD_NFTS: Map<(Addr, String), HeldNft> = Map::new("held_nfts");
+pub const AUCTION_COUNT: Item<u64> = Item::new("auction_count");
+pub const AUCTIONS: Map<u64, Auction> = Map::new("auctions");
+pub const STATS: Item<Stats> = Item::new("stats");
+// Disputes over frozen beneficiaries, the active freeze of a beneficiary is in FROZEN_BENEFICIARIES
+pub const DISPUTE_PERIOD: Item<u64> = Item::new("dispute_period");
+pub const FREEZE_COUNT: Item<u64> = Item::new("freeze_count");
+pub const FREEZES: Map<u64, Freeze> = Map::new("freezes");
+pub const FROZEN_BENEFICIARIES: Map<Addr, u64> = Map::new("frozen_beneficiaries");
+// What each donator paid a beneficiary directly, keyed by (beneficiary, donator), and the totals
+// per beneficiary. Only donators which can be refunded are counted
+pub const BENEFICIARY_PAYERS: Map<(Addr, Addr), Vec<Coin>> = Map::new("beneficiary_payers");
+pub const BENEFICIARY_PAID: Map<Addr, Vec<Coin>> = Map::new("beneficiary_paid");
+// Balances of frozen beneficiaries still to be returned to their donators
+pub const PENDING_REFUNDS: Item<Vec<Coin>> = Item::new("pending_refunds");
+// Highest bids of the open auctions
+pub const ESCROWED_BIDS: Item<Vec<Coin>> = Item::new("escrowed_bids");
+// Last beneficiary paid by the round-robin strategy
+pub const ROUND_ROBIN_CURSOR: Item<Addr> = Item::new("round_robin_cursor");
+
+// Quadratic funding rounds, each round keeps its own donators / beneficiaries accounting
+pub const ROUND_COUNT: Item<u64> = Item::new("round_count");
+pub const ROUNDS: Map<u64, Round> = Map::new("rounds");
+pub const ROUND_DONATORS: Map<(u64, Addr), Vec<Coin>> = Map::new("round_donators");
+pub const ROUND_BENEFICIARIES: Map<(u64, Addr), Vec<Coin>> = Map::new("round_beneficiaries");
+// (round_id, beneficiary, donator) => total contributed by donator to beneficiary
+pub const ROUND_CONTRIBUTIONS: Map<(u64, Addr, Addr), Uint128> = Map::new("round_contributions");
+// (round_id, funder) => put in the matching pool, until the leftover is reclaimed
+pub const ROUND_FUNDERS: Map<(u64, Addr), Uint128> = Map::new("round_funders");
+
+// Optional governance over the beneficiaries set, disabled when not set
+pub const GOVERNANCE: Item<GovernanceConfig> = Item::new("governance");
+pub const PROPOSAL_COUNT: Item<u64> = Item::new("proposal_count");
+pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals");
+// (proposal_id, voter) => approved
+pub const VOTES: Map<(u64, Addr), bool> = Map::new("votes");
+// (voter set, voter) => weight, and voter set => total weight, kept at every height so that
+// proposals are voted on with the weights they were made with. Only written while governance
+// is enabled
+pub const VOTING_WEIGHTS: SnapshotMap<(&str, Addr), Uint128> = SnapshotMap::new(
+    "voting_weights",
+    "voting_weights__checkpoints",
+    "voting_weights__changelog",
+    Strategy::EveryBlock,
+);
+pub const TOTAL_VOTING_WEIGHTS: SnapshotMap<&str, Uint128> = SnapshotMap::new(
+    "total_voting_weights",
+    "total_voting_weights__checkpoints",
+    "total_voting_weights__changelog",
+    Strategy::EveryBlock,
+);
+
+// Delay in seconds applied to sensitive operations, disabled when not set or zero
+pub const TIMELOCK_DELAY: Item<u64> = Item::new("timelock_delay");
+pub const OPERATION_COUNT: Item<u64> = Item::new("operation_count");
+pub const PENDING_OPERATIONS: Map<u64, PendingOperation> = Map::new("pending_operations");